@@ -0,0 +1,696 @@
+//! Build script.
+//! Generates the CPU model tables from the data files.
+
+
+// This Source Code Form is subject to the terms of the
+// Mozilla Public License, v. 2.0. If a copy of the MPL
+// was not distributed with this file, You can obtain one
+// at https://mozilla.org/MPL/2.0/.
+
+
+
+use std::{
+	env,
+	fmt::Write as _,
+	fs,
+	path::Path,
+};
+
+
+
+fn main() {
+	let out = env::var("OUT_DIR").expect("OUT_DIR not set");
+
+	let mut code = String::from("// Generated by the build script from `data/uarch.txt` and `data/models.txt`. Do not edit.\n\n");
+
+	let uarchs = uarchs(&mut code);
+
+	models(&mut code, &uarchs);
+
+	fs::write(Path::new(&out).join("models.rs"), code).expect("Could not write the model table");
+
+	fs::write(Path::new(&out).join("errata.rs"), errata()).expect("Could not write the errata table");
+
+	fs::write(Path::new(&out).join("features.rs"), features()).expect("Could not write the feature table");
+}
+
+
+
+/// A line of `data/uarch.txt`.
+struct UarchRow {
+	lineage: String,
+	generation: u8,
+	name: String,
+	variant: String,
+	class: String,
+	profile: String,
+}
+
+
+
+/// A line of `data/models.txt`.
+struct ModelRow {
+	vendors: Vec<String>,
+	family: u16,
+	model: (u8, u8),
+	stepping: Option<(u8, u8)>,
+	variant: String,
+	uarch: String,
+	secondary: Option<String>,
+	codenames: Vec<String>,
+	node: Vec<u16>,
+	segments: Vec<String>,
+	products: Vec<String>,
+	year: Option<u16>,
+	core: String,
+	profile: String,
+	llvm: Option<String>,
+}
+
+
+
+/// A line of `data/errata.txt`.
+struct ErratumRow {
+	id: String,
+	vendor: String,
+	family: u16,
+	model: (u8, u8),
+	stepping: Option<(u8, u8)>,
+	microcode: Option<(u32, u32)>,
+	impact: String,
+	title: String,
+	workaround: String,
+}
+
+
+
+/// A line of `data/features.txt`.
+struct FeatureRow {
+	set: String,
+	flag: String,
+	requires: Option<String>,
+	rustc: Option<String>,
+	gcc: Option<String>,
+	llvm: String,
+}
+
+
+
+/// Generated accessor: documentation, name, return type and value of a row.
+type Accessor = (&'static str, &'static str, &'static str, fn(&UarchRow) -> String);
+
+
+/// Generates the `Uarch` enum.
+fn uarchs(code: &mut String) -> Vec<UarchRow> {
+	const SOURCE: &str = "data/uarch.txt";
+
+	println!("cargo:rerun-if-changed={}", SOURCE);
+
+	let data = fs::read_to_string(SOURCE).expect("Could not read the microarchitecture database");
+
+	let mut rows: Vec<UarchRow> = Vec::new();
+
+	for (n, line) in lines(&data) {
+		let row = parse_uarch(line).unwrap_or_else(|e| panic!("{}:{}: {}", SOURCE, n, e));
+
+		if rows.iter().any(|r| r.variant == row.variant) {
+			panic!("{}:{}: duplicate microarchitecture: {:?}", SOURCE, n, row.name);
+		}
+
+		rows.push(row);
+	}
+
+	// Enum.
+	writeln!(code, "/// Microarchitecture of the CPU cores.").unwrap();
+	writeln!(code, "#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]").unwrap();
+	writeln!(code, "pub enum Uarch {{").unwrap();
+
+	let mut group = "";
+
+	for row in &rows {
+		if row.lineage != group {
+			writeln!(code, "\t// {}", row.lineage).unwrap();
+			group = &row.lineage;
+		}
+
+		writeln!(code, "\t{},", row.variant).unwrap();
+	}
+
+	writeln!(code, "}}\n").unwrap();
+
+	// Accessors.
+	writeln!(code, "impl Uarch {{").unwrap();
+
+	let accessors: [Accessor; 5] = [
+		("Returns the name of the microarchitecture.", "name", "&'static str", |r| format!("{:?}", r.name)),
+		("Returns the lineage of the microarchitecture.", "lineage", "Lineage", |r| format!("Lineage::{}", r.lineage)),
+		("Returns the rank of the microarchitecture inside its lineage.", "generation", "u8", |r| r.generation.to_string()),
+		("Returns the class of the cores.", "class", "CoreClass", |r| format!("CoreClass::{}", r.class)),
+		("Returns the execution profile of the microarchitecture.", "profile", "Profile", |r| r.profile.clone()),
+	];
+
+	for (doc, name, ty, value) in accessors.iter() {
+		writeln!(code, "\t/// {}", doc).unwrap();
+		writeln!(code, "\tpub fn {}(&self) -> {} {{", name, ty).unwrap();
+		writeln!(code, "\t\tmatch *self {{").unwrap();
+
+		for row in &rows {
+			writeln!(code, "\t\t\tUarch::{} => {},", row.variant, value(row)).unwrap();
+		}
+
+		writeln!(code, "\t\t}}\n\t}}\n").unwrap();
+	}
+
+	writeln!(code, "}}\n").unwrap();
+
+	rows
+}
+
+
+/// Generates the `CPUModel` enum and the model table.
+fn models(code: &mut String, uarchs: &[UarchRow]) {
+	const SOURCE: &str = "data/models.txt";
+
+	println!("cargo:rerun-if-changed={}", SOURCE);
+
+	let data = fs::read_to_string(SOURCE).expect("Could not read the model database");
+
+	let rows: Vec<ModelRow> = lines(&data)
+		.map(|(n, line)| parse_model(line, uarchs).unwrap_or_else(|e| panic!("{}:{}: {}", SOURCE, n, e)))
+		.collect();
+
+	lookups(&rows);
+
+	// Variants in order of first appearance.
+	let mut variants: Vec<(&str, &str)> = Vec::new();
+
+	for row in &rows {
+		if !variants.iter().any(|(v, _)| *v == row.variant) {
+			variants.push((&row.variant, &row.vendors[0]));
+		}
+	}
+
+	// Enum.
+	writeln!(code, "/// Microarchitecture or product line of the CPU.").unwrap();
+	writeln!(code, "/// Each variant carries the decoded model number.").unwrap();
+	writeln!(code, "#[derive(Debug, Clone, Copy, PartialEq, Eq)]").unwrap();
+	writeln!(code, "pub enum CPUModel {{").unwrap();
+
+	let mut group = "";
+
+	for (variant, vendor) in &variants {
+		if *vendor != group {
+			writeln!(code, "\t// {}", vendor).unwrap();
+			group = vendor;
+		}
+
+		writeln!(code, "\t{}(u8),", variant).unwrap();
+	}
+
+	writeln!(code, "\n\tUnknown(u8),\n}}\n").unwrap();
+
+	// Model number accessor.
+	writeln!(code, "impl CPUModel {{").unwrap();
+	writeln!(code, "\t/// Returns the model number carried by the variant.").unwrap();
+	writeln!(code, "\tpub fn number(&self) -> u8 {{").unwrap();
+	writeln!(code, "\t\tmatch *self {{").unwrap();
+
+	for (variant, _) in &variants {
+		writeln!(code, "\t\t\tCPUModel::{}(n) => n,", variant).unwrap();
+	}
+
+	writeln!(code, "\t\t\tCPUModel::Unknown(n) => n,").unwrap();
+	writeln!(code, "\t\t}}\n\t}}\n}}\n").unwrap();
+
+	// Table.
+	let entries: usize = rows.iter().map(|r| r.vendors.len()).sum();
+
+	writeln!(code, "static MODELS: [ModelEntry; {}] = [", entries).unwrap();
+
+	for row in &rows {
+		for vendor in &row.vendors {
+			let stepping = match row.stepping {
+				Some((lo, hi)) => format!("Some((0x{:02X}, 0x{:02X}))", lo, hi),
+				None => String::from("None"),
+			};
+
+			let year = match row.year {
+				Some(year) => format!("Some({})", year),
+				None => String::from("None"),
+			};
+
+			let secondary = match &row.secondary {
+				Some(uarch) => format!("Some(Uarch::{})", uarch),
+				None => String::from("None"),
+			};
+
+			let segments: Vec<String> = row.segments.iter().map(|s| format!("Segment::{}", s)).collect();
+
+			writeln!(code,
+				"\tModelEntry {{ vendor: CPUVendor::{}, family: 0x{:02X}, model: (0x{:02X}, 0x{:02X}), stepping: {}, build: CPUModel::{}, info: ModelInfo::new(Uarch::{}, {}, &{:?}, &{:?}, &[{}], &{:?}, {}, CoreType::{}), profile: {}, llvm: {:?} }},",
+				vendor, row.family, row.model.0, row.model.1, stepping, row.variant,
+				row.uarch, secondary, row.codenames, row.node, segments.join(", "), row.products, year, row.core, row.profile, row.llvm,
+			).unwrap();
+		}
+	}
+
+	writeln!(code, "];").unwrap();
+}
+
+
+/// Checks that the rows of a variant and model number share their metadata.
+/// `CPUModel::entry` looks the metadata up by variant and model number alone.
+fn lookups(rows: &[ModelRow]) {
+	for (i, a) in rows.iter().enumerate() {
+		for b in rows[i + 1..].iter().filter(|b| b.variant == a.variant) {
+			if (b.model.1 < a.model.0) || (a.model.1 < b.model.0) { continue; }
+
+			let same = (a.uarch == b.uarch) && (a.secondary == b.secondary) && (a.codenames == b.codenames)
+				&& (a.node == b.node) && (a.segments == b.segments) && (a.products == b.products)
+				&& (a.year == b.year) && (a.core == b.core) && (a.profile == b.profile) && (a.llvm == b.llvm);
+
+			if !same {
+				panic!("data/models.txt: {}({:?}) and {}({:?}) share a model number with different metadata, give them their own variants", a.variant, a.codenames, b.variant, b.codenames);
+			}
+		}
+	}
+}
+
+
+/// Generates the errata table.
+fn errata() -> String {
+	const SOURCE: &str = "data/errata.txt";
+
+	println!("cargo:rerun-if-changed={}", SOURCE);
+
+	let data = fs::read_to_string(SOURCE).expect("Could not read the errata database");
+
+	let rows: Vec<ErratumRow> = lines(&data)
+		.map(|(n, line)| parse_erratum(line).unwrap_or_else(|e| panic!("{}:{}: {}", SOURCE, n, e)))
+		.collect();
+
+	let mut code = format!("// Generated by the build script from `{}`. Do not edit.\n\n", SOURCE);
+
+	writeln!(code, "static ERRATA: [Erratum; {}] = [", rows.len()).unwrap();
+
+	for row in &rows {
+		let stepping = match row.stepping {
+			Some((lo, hi)) => format!("Some((0x{:02X}, 0x{:02X}))", lo, hi),
+			None => String::from("None"),
+		};
+
+		let microcode = match row.microcode {
+			Some((lo, hi)) => format!("Some((0x{:X}, 0x{:X}))", lo, hi),
+			None => String::from("None"),
+		};
+
+		writeln!(code,
+			"\tErratum {{ id: {:?}, vendor: CPUVendor::{}, family: 0x{:02X}, model: (0x{:02X}, 0x{:02X}), stepping: {}, microcode: {}, impact: Impact::{}, title: {:?}, workaround: {:?} }},",
+			row.id, row.vendor, row.family, row.model.0, row.model.1, stepping, microcode, row.impact, row.title, row.workaround,
+		).unwrap();
+	}
+
+	writeln!(code, "];").unwrap();
+
+	code
+}
+
+
+/// Generates the feature table.
+fn features() -> String {
+	const SOURCE: &str = "data/features.txt";
+
+	println!("cargo:rerun-if-changed={}", SOURCE);
+
+	let data = fs::read_to_string(SOURCE).expect("Could not read the feature table");
+
+	let mut rows: Vec<FeatureRow> = Vec::new();
+
+	for (n, line) in lines(&data) {
+		let row = parse_feature(line).unwrap_or_else(|e| panic!("{}:{}: {}", SOURCE, n, e));
+
+		let names = |r: &FeatureRow| [r.rustc.clone(), Some(r.llvm.clone())];
+
+		if rows.iter().any(|r| names(r).iter().flatten().any(|name| names(&row).iter().flatten().any(|other| name == other))) {
+			panic!("{}:{}: duplicate feature name: {:?}", SOURCE, n, row.llvm);
+		}
+
+		rows.push(row);
+	}
+
+	names(&rows);
+
+	let mut code = format!("// Generated by the build script from `{}`. Do not edit.\n\n", SOURCE);
+
+	// The cache keeps one bit per feature and a ready bit in an `AtomicU64`.
+	if rows.len() > 63 {
+		panic!("{}: the feature table is limited to 63 features", SOURCE);
+	}
+
+	// A constant rather than a static so that `Feature::find` can read it.
+	writeln!(code, "const FEATURES: &[Feature] = &[").unwrap();
+
+	for (index, row) in rows.iter().enumerate() {
+		let flag = match row.set.as_str() {
+			"ISA" => format!("ISAFlags::{}", row.flag),
+			_ => format!("SIMDFlags::{}", row.flag),
+		};
+
+		let requires = match &row.requires {
+			Some(r) => format!("Some(SIMDFlags::{})", r),
+			None => String::from("None"),
+		};
+
+		writeln!(code,
+			"\tFeature {{ index: {}, set: FeatureSet::{}, flag: {}, requires: {}, rustc: {:?}, gcc: {:?}, llvm: {:?} }},",
+			index, row.set, flag, requires, row.rustc, row.gcc, row.llvm,
+		).unwrap();
+	}
+
+	writeln!(code, "];").unwrap();
+
+	code
+}
+
+
+/// Checks the rustc names shipped with the macro crate against the feature table.
+/// Skipped when the crate is built alone, outside the workspace.
+fn names(rows: &[FeatureRow]) {
+	const NAMES: &str = "macros/features.txt";
+
+	if !Path::new(NAMES).exists() { return; }
+
+	println!("cargo:rerun-if-changed={}", NAMES);
+
+	let data = fs::read_to_string(NAMES).expect("Could not read the rustc feature names");
+
+	let listed: Vec<&str> = lines(&data).map(|(_, line)| line).collect();
+	let expected: Vec<&str> = rows.iter().filter_map(|r| r.rustc.as_deref()).collect();
+
+	if listed != expected {
+		panic!("{} is out of date, it must list in order: {}", NAMES, expected.join(" "));
+	}
+}
+
+
+/// Parses a line of the microarchitecture database.
+fn parse_uarch(line: &str) -> Result<UarchRow, String> {
+	let fields: Vec<&str> = line.split('|').map(str::trim).collect();
+
+	if fields.len() != 5 { return Err(format!("expected 5 columns, found {}", fields.len())); }
+
+	let lineage = String::from(fields[0]);
+
+	if !LINEAGES.contains(&lineage.as_str()) { return Err(format!("invalid lineage: {:?}", lineage)); }
+
+	let generation = fields[1].parse::<u8>().map_err(|e| format!("invalid generation {:?}: {}", fields[1], e))?;
+
+	let name = String::from(fields[2]);
+
+	// Variant name: words capitalized and joined, `+` spelled `Plus`.
+	let variant: String = name.replace('+', " Plus")
+		.split_whitespace()
+		.map(|word| {
+			let mut chars = word.chars();
+			chars.next().map(|c| c.to_ascii_uppercase().to_string() + chars.as_str()).unwrap_or_default()
+		})
+		.collect();
+
+	if !variant.starts_with(|c: char| c.is_ascii_alphabetic()) || !variant.chars().all(|c| c.is_ascii_alphanumeric()) {
+		return Err(format!("invalid microarchitecture name: {:?}", name));
+	}
+
+	let class = String::from(fields[3]);
+
+	if !CLASSES.contains(&class.as_str()) { return Err(format!("invalid core class: {:?}", class)); }
+
+	let profile = parse_profile(fields[4])?;
+
+	Ok( UarchRow { lineage, generation, name, variant, class, profile } )
+}
+
+
+/// Parses a line of the model database.
+fn parse_model(line: &str, uarchs: &[UarchRow]) -> Result<ModelRow, String> {
+	let fields: Vec<&str> = line.split('|').map(str::trim).collect();
+
+	if fields.len() != 15 { return Err(format!("expected 15 columns, found {}", fields.len())); }
+
+	let vendors = fields[0].split(',').map(|v| String::from(v.trim())).collect();
+
+	let family = parse_hex(fields[1])?;
+	if family > 0x10E { return Err(format!("family out of range: {}", fields[1])); }
+
+	let model = parse_range(fields[2])?;
+
+	let stepping = match fields[3] {
+		"*" => None,
+		s => {
+			let range = parse_range(s)?;
+			if range.1 > 0x0F { return Err(format!("stepping out of range: {}", s)); }
+			Some(range)
+		},
+	};
+
+	let variant = String::from(fields[4]);
+
+	if variant.is_empty() || !variant.chars().all(|c| c.is_ascii_alphanumeric()) {
+		return Err(format!("invalid variant name: {:?}", variant));
+	}
+
+	let uarch = match uarchs.iter().find(|u| u.name == fields[5]) {
+		Some(u) => u,
+		None => return Err(format!("unknown microarchitecture: {:?}", fields[5])),
+	};
+
+	let secondary = match fields[6] {
+		"-" => None,
+		name => match uarchs.iter().find(|u| u.name == name) {
+			Some(u) => Some(u.variant.clone()),
+			None => return Err(format!("unknown microarchitecture: {:?}", name)),
+		},
+	};
+
+	let codenames = list(fields[7]);
+
+	let node = list(fields[8]).iter()
+		.map(|n| n.parse::<u16>().map_err(|e| format!("invalid node {:?}: {}", n, e)))
+		.collect::<Result<_, _>>()?;
+
+	let segments = list(fields[9]);
+
+	if let Some(s) = segments.iter().find(|s| !SEGMENTS.contains(&s.as_str())) {
+		return Err(format!("invalid segment: {:?}", s));
+	}
+
+	let products = list(fields[10]);
+
+	let year = match fields[11] {
+		"-" => None,
+		s => Some( s.parse::<u16>().map_err(|e| format!("invalid year {:?}: {}", s, e))? ),
+	};
+
+	let core = String::from(fields[12]);
+
+	if !CORES.contains(&core.as_str()) { return Err(format!("invalid core type: {:?}", core)); }
+
+	if (core == "Hybrid") != secondary.is_some() {
+		return Err(String::from("hybrid models, and only them, need a secondary microarchitecture"));
+	}
+
+	let profile = match fields[13] {
+		"-" => uarch.profile.clone(),
+		s => parse_profile(s)?,
+	};
+
+	let llvm = match fields[14] {
+		"-" => None,
+		s if s.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || (c == '-')) => Some(String::from(s)),
+		s => return Err(format!("invalid LLVM CPU name: {:?}", s)),
+	};
+
+	let uarch = uarch.variant.clone();
+
+	Ok( ModelRow { vendors, family: family as u16, model, stepping, variant, uarch, secondary, codenames, node, segments, products, year, core, profile, llvm } )
+}
+
+
+/// Parses a line of the errata database.
+fn parse_erratum(line: &str) -> Result<ErratumRow, String> {
+	let fields: Vec<&str> = line.split('|').map(str::trim).collect();
+
+	if fields.len() != 9 { return Err(format!("expected 9 columns, found {}", fields.len())); }
+
+	let id = String::from(fields[0]);
+
+	if id.is_empty() { return Err(String::from("missing identifier")); }
+
+	let vendor = String::from(fields[1]);
+
+	let family = parse_hex(fields[2])?;
+	if family > 0x10E { return Err(format!("family out of range: {}", fields[2])); }
+
+	let model = parse_range(fields[3])?;
+
+	let stepping = match fields[4] {
+		"*" => None,
+		s => {
+			let range = parse_range(s)?;
+			if range.1 > 0x0F { return Err(format!("stepping out of range: {}", s)); }
+			Some(range)
+		},
+	};
+
+	let microcode = match fields[5] {
+		"*" => None,
+		s => Some( parse_span(s, u32::MAX)? ),
+	};
+
+	let impact = String::from(fields[6]);
+
+	if !IMPACTS.contains(&impact.as_str()) { return Err(format!("invalid impact: {:?}", impact)); }
+
+	let title = String::from(fields[7]);
+	let workaround = String::from(fields[8]);
+
+	Ok( ErratumRow { id, vendor, family: family as u16, model, stepping, microcode, impact, title, workaround } )
+}
+
+/// Parses a line of the feature table.
+fn parse_feature(line: &str) -> Result<FeatureRow, String> {
+	let fields: Vec<&str> = line.split('|').map(str::trim).collect();
+
+	if fields.len() != 6 { return Err(format!("expected 6 columns, found {}", fields.len())); }
+
+	let set = String::from(fields[0]);
+
+	if !SETS.contains(&set.as_str()) { return Err(format!("invalid flag set: {:?}", set)); }
+
+	let constant = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+
+	let flag = String::from(fields[1]);
+
+	if !constant(&flag) { return Err(format!("invalid flag: {:?}", flag)); }
+
+	let requires = match fields[2] {
+		"-" => None,
+		s if constant(s) => Some(String::from(s)),
+		s => return Err(format!("invalid required flag: {:?}", s)),
+	};
+
+	let name = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || (c == '.') || (c == '-'));
+
+	let rustc = match fields[3] {
+		"-" => None,
+		s if name(s) => Some(String::from(s)),
+		s => return Err(format!("invalid rustc name: {:?}", s)),
+	};
+
+	let gcc = match fields[4] {
+		"-" => None,
+		s if name(s) => Some(String::from(s)),
+		s => return Err(format!("invalid GCC name: {:?}", s)),
+	};
+
+	let llvm = match fields[5] {
+		s if name(s) => String::from(s),
+		s => return Err(format!("invalid LLVM name: {:?}", s)),
+	};
+
+	Ok( FeatureRow { set, flag, requires, rustc, gcc, llvm } )
+}
+
+
+/// Parses a `width/fma/caveats` execution profile into its constructor.
+fn parse_profile(s: &str) -> Result<String, String> {
+	let fields: Vec<&str> = s.split('/').map(str::trim).collect();
+
+	if fields.len() != 3 { return Err(format!("invalid profile: {:?}", s)); }
+
+	let width = fields[0].parse::<u16>().map_err(|e| format!("invalid width {:?}: {}", fields[0], e))?;
+
+	if ![0, 64, 128, 256, 512].contains(&width) { return Err(format!("invalid width: {}", width)); }
+
+	let fma = fields[1].parse::<u8>().map_err(|e| format!("invalid FMA count {:?}: {}", fields[1], e))?;
+
+	let caveats = list(fields[2]);
+
+	if let Some(c) = caveats.iter().find(|c| !CAVEATS.contains(&c.as_str())) {
+		return Err(format!("invalid caveat: {:?}", c));
+	}
+
+	let caveats: Vec<String> = caveats.iter().map(|c| format!("Caveat::{}", c)).collect();
+
+	Ok( format!("Profile::new({}, {}, &[{}])", width, fma, caveats.join(", ")) )
+}
+
+
+
+/// Variants of `Lineage`.
+const LINEAGES: [&str; 16] = [
+	"IntelCore", "NetBurst", "Quark", "IntelAtom", "IntelPhi",
+	"AMD", "AMDCat", "Centaur", "CNS", "Cyrix", "Geode",
+	"NexGen", "Transmeta", "Rise", "UMC", "Vortex86",
+];
+
+/// Variants of `CoreClass`.
+const CLASSES: [&str; 2] = ["Big", "Small"];
+
+/// Variants of `Caveat`.
+const CAVEATS: [&str; 6] = ["SplitFP128", "SplitFP256", "SplitFP512", "SingleFMA512", "AVX512Throttle", "SlowPdepPext"];
+
+/// Variants of `Impact`.
+const IMPACTS: [&str; 5] = ["Correctness", "Security", "Stability", "Performance", "Feature"];
+
+/// Variants of `Segment`.
+const SEGMENTS: [&str; 4] = ["Desktop", "Mobile", "Server", "Embedded"];
+
+/// Variants of `FeatureSet`.
+const SETS: [&str; 3] = ["ISA", "SIMD", "AVX512"];
+
+/// Variants of `CoreType`.
+const CORES: [&str; 4] = ["Performance", "Efficiency", "Dense", "Hybrid"];
+
+
+
+/// Iterates over the non empty, non comment lines with their line number.
+fn lines(data: &str) -> impl Iterator<Item = (usize, &str)> {
+	data.lines()
+		.enumerate()
+		.map(|(n, line)| (n + 1, line.trim()))
+		.filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+}
+
+/// Splits a `,` separated list, `-` being the empty list.
+fn list(s: &str) -> Vec<String> {
+	match s {
+		"-" | "" => Vec::new(),
+		s => s.split(',').map(|v| String::from(v.trim())).collect(),
+	}
+}
+
+/// Parses a hexadecimal value with a `0x` prefix.
+fn parse_hex(s: &str) -> Result<u32, String> {
+	let digits = s.strip_prefix("0x").ok_or_else(|| format!("expected a 0x prefix: {}", s))?;
+
+	u32::from_str_radix(digits, 16).map_err(|e| format!("{}: {}", s, e))
+}
+
+/// Parses an hexadecimal byte or an inclusive `lo-hi` range of bytes.
+fn parse_range(s: &str) -> Result<(u8, u8), String> {
+	let (lo, hi) = parse_span(s, 0xFF)?;
+
+	Ok( (lo as u8, hi as u8) )
+}
+
+/// Parses an hexadecimal value or an inclusive `lo-hi` range up to `max`.
+fn parse_span(s: &str, max: u32) -> Result<(u32, u32), String> {
+	let (lo, hi) = match s.split_once('-') {
+		Some((lo, hi)) => (parse_hex(lo)?, parse_hex(hi)?),
+		None => { let v = parse_hex(s)?; (v, v) },
+	};
+
+	if (lo > hi) || (hi > max) { return Err(format!("invalid range: {}", s)); }
+
+	Ok( (lo, hi) )
+}
@@ -0,0 +1,231 @@
+//! Procedural macros of `micro-cpuflags`.
+
+// This Source Code Form is subject to the terms of the
+// Mozilla Public License, v. 2.0. If a copy of the MPL
+// was not distributed with this file, You can obtain one
+// at https://mozilla.org/MPL/2.0/.
+
+
+
+use proc_macro::TokenStream;
+
+use proc_macro2::{ Span, TokenTree };
+
+use quote::{ format_ident, quote };
+
+use syn::{ FnArg, Ident, ItemFn, Pat, PatIdent, PatType, Type };
+
+
+
+/// rustc names of the features of the crate, checked against the feature
+/// table by the build script of `micro-cpuflags`.
+const FEATURES: &str = include_str!("../features.txt");
+
+
+
+/// Compiles a function once per feature set and selects the best clone at
+/// runtime with a `micro_cpuflags::dispatch::Dispatcher`.
+///
+/// Feature sets are given from the best to the worst, features of a set are
+/// joined by `+` and named as in rustc. `fallback` is a clone compiled
+/// without extra features, it is required and must be last so a clone is
+/// always usable.
+///
+/// ```ignore
+/// #[cpu_dispatch(avx512f+avx512bw, avx2+fma, sse4.2, fallback)]
+/// fn sum(data: &[u8]) -> u32 {
+///     data.iter().map(|&x| x as u32).sum()
+/// }
+/// ```
+///
+/// On other architectures than x86 and x86-64 only the fallback is compiled.
+/// Generic functions, methods, `async` and `const` functions are not supported.
+#[proc_macro_attribute]
+pub fn cpu_dispatch(attr: TokenStream, item: TokenStream) -> TokenStream {
+	let item = syn::parse_macro_input!(item as ItemFn);
+
+	match expand(attr.into(), item) {
+		Ok(tokens) => tokens.into(),
+		Err(e) => e.to_compile_error().into(),
+	}
+}
+
+
+
+/// Feature set of a clone, empty for the fallback.
+struct Variant {
+	/// rustc names of the features.
+	features: Vec<String>,
+
+	/// Span of the set, for errors.
+	span: Span,
+}
+
+
+
+/// Expands `#[cpu_dispatch]`.
+fn expand(attr: proc_macro2::TokenStream, item: ItemFn) -> syn::Result<proc_macro2::TokenStream> {
+	let variants = variants(attr)?;
+
+	let sig = &item.sig;
+
+	if let Some(t) = sig.constness { return Err(syn::Error::new_spanned(t, "`const` functions are not supported")); }
+	if let Some(t) = sig.asyncness { return Err(syn::Error::new_spanned(t, "`async` functions are not supported")); }
+
+	if !sig.generics.params.is_empty() || sig.generics.where_clause.is_some() {
+		return Err(syn::Error::new_spanned(&sig.generics, "generic functions are not supported"));
+	}
+
+	if let Some(variadic) = &sig.variadic {
+		return Err(syn::Error::new_spanned(variadic, "variadic functions are not supported"));
+	}
+
+	// Arguments of the dispatching function, renamed as their patterns may not be identifiers.
+	let mut types: Vec<Type> = Vec::new();
+	let mut names: Vec<Ident> = Vec::new();
+	let mut inputs = sig.inputs.clone();
+
+	for (i, input) in inputs.iter_mut().enumerate() {
+		match input {
+			FnArg::Receiver(receiver) => return Err(syn::Error::new_spanned(receiver, "methods are not supported")),
+
+			FnArg::Typed(PatType { pat, ty, .. }) => {
+				let name = format_ident!("__cpu_dispatch_arg{}", i);
+
+				**pat = Pat::Ident(PatIdent { attrs: Vec::new(), by_ref: None, mutability: None, ident: name.clone(), subpat: None });
+
+				types.push((**ty).clone());
+				names.push(name);
+			},
+		}
+	}
+
+	let output = &sig.output;
+	let abi    = &sig.abi;
+	let unsafety = &sig.unsafety;
+
+	let clone = |i: usize, variant: &Variant| {
+		let mut sig = sig.clone();
+		sig.ident = format_ident!("__cpu_dispatch_{}", i);
+
+		let block = &item.block;
+
+		match variant.features.is_empty() {
+			true => quote! { #sig #block },
+
+			_ => {
+				sig.unsafety = Some(Default::default());
+
+				let enable = variant.features.join(",");
+
+				quote! {
+					#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+					#[target_feature(enable = #enable)]
+					#sig #block
+				}
+			},
+		}
+	};
+
+	let clones = variants.iter().enumerate().map(|(i, v)| clone(i, v));
+
+	let entry = |(i, v): (usize, &Variant)| {
+		let ident = format_ident!("__cpu_dispatch_{}", i);
+		let features = &v.features;
+
+		quote! { (&[#(#features),*], #ident) }
+	};
+
+	let entries = variants.iter().enumerate().map(entry);
+
+	// Other architectures only have the fallback.
+	let fallback = variants.iter().enumerate().filter(|(_, v)| v.features.is_empty()).map(entry);
+
+	let attrs = &item.attrs;
+	let vis   = &item.vis;
+	let ident = &sig.ident;
+
+	Ok(quote! {
+		#(#attrs)*
+		#vis #unsafety #abi fn #ident(#inputs) #output {
+			#(#clones)*
+
+			#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+			static DISPATCHER: ::micro_cpuflags::dispatch::Dispatcher<unsafe #abi fn(#(#types),*) #output> =
+				::micro_cpuflags::dispatch::Dispatcher::new(&[#(#entries),*]);
+
+			#[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+			static DISPATCHER: ::micro_cpuflags::dispatch::Dispatcher<unsafe #abi fn(#(#types),*) #output> =
+				::micro_cpuflags::dispatch::Dispatcher::new(&[#(#fallback),*]);
+
+			// The dispatcher only selects clones whose features are usable.
+			#[allow(unused_unsafe)]
+			unsafe { (DISPATCHER.get())(#(#names),*) }
+		}
+	})
+}
+
+
+/// Parses and validates the feature sets of the attribute.
+fn variants(attr: proc_macro2::TokenStream) -> syn::Result<Vec<Variant>> {
+	let mut variants: Vec<Variant> = Vec::new();
+
+	// Feature sets and names are split on tokens, as names like `sse4.2` are several tokens.
+	let mut sets: Vec<Vec<Vec<TokenTree>>> = vec![vec![Vec::new()]];
+
+	for token in attr {
+		match &token {
+			TokenTree::Punct(p) if p.as_char() == ',' => sets.push(vec![Vec::new()]),
+			TokenTree::Punct(p) if p.as_char() == '+' => sets.last_mut().unwrap().push(Vec::new()),
+			_ => sets.last_mut().unwrap().last_mut().unwrap().push(token),
+		}
+	}
+
+	// Trailing comma.
+	if sets.len() > 1 && sets.last().is_some_and(|set| (set.len() == 1) && set[0].is_empty()) {
+		sets.pop();
+	}
+
+	for set in sets {
+		let span = set.first().and_then(|tokens| tokens.first()).map_or(Span::call_site(), |t| t.span());
+
+		let mut features = Vec::new();
+
+		for tokens in set {
+			let name: String = tokens.iter().map(|t| t.to_string()).collect();
+
+			if name.is_empty() { return Err(syn::Error::new(span, "expected a feature name")); }
+
+			if !known(&name) && (name != "fallback") {
+				return Err(syn::Error::new(tokens[0].span(), format!("unknown feature `{}`", name)));
+			}
+
+			features.push(name);
+		}
+
+		if let Some(last) = variants.last() {
+			if last.features.is_empty() { return Err(syn::Error::new(last.span, "`fallback` must be the last variant")); }
+		}
+
+		match features.iter().any(|name| name == "fallback") {
+			true if features.len() > 1 => return Err(syn::Error::new(span, "`fallback` can not be combined with features")),
+			true => variants.push(Variant { features: Vec::new(), span }),
+			_ => variants.push(Variant { features, span }),
+		}
+	}
+
+	match variants.last() {
+		Some(last) if last.features.is_empty() => Ok(variants),
+		Some(last) => Err(syn::Error::new(last.span, "the last variant must be `fallback`")),
+		_ => Err(syn::Error::new(Span::call_site(), "expected feature sets and `fallback`")),
+	}
+}
+
+
+/// Returns `true` if rustc knows the feature, as listed in the feature table.
+fn known(name: &str) -> bool {
+	FEATURES.lines()
+		.map(str::trim)
+		.filter(|line| !line.is_empty() && !line.starts_with('#'))
+		.any(|rustc| rustc == name)
+}
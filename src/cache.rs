@@ -0,0 +1,295 @@
+//! Process-wide cache of the host detection.
+//! CPUID is executed once per process, every decoder reads the same dump.
+
+
+// This Source Code Form is subject to the terms of the
+// Mozilla Public License, v. 2.0. If a copy of the MPL
+// was not distributed with this file, You can obtain one
+// at https://mozilla.org/MPL/2.0/.
+
+
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{ AtomicU64, AtomicU8, Ordering };
+
+use crate::cpuid::CPUID;
+use crate::error::Error;
+use crate::feature::Feature;
+use crate::info::Model;
+use crate::isa::ISAFlags;
+use crate::simd::SIMDFlags;
+use crate::target::Target;
+
+
+
+/// States of the snapshot.
+const EMPTY: u8 = 0;
+const BUSY:  u8 = 1;
+const READY: u8 = 2;
+
+/// Bit of `DETECTED` set once the features are cached.
+const CACHED: u64 = 1 << 63;
+
+
+
+/// Snapshot of the host, written once.
+static SNAPSHOT: Global = Global { state: AtomicU8::new(EMPTY), snapshot: UnsafeCell::new(MaybeUninit::uninit()) };
+
+/// Usable features of the host, one bit per entry of the feature table.
+static DETECTED: AtomicU64 = AtomicU64::new(0);
+
+
+
+/// Returns the snapshot of the host, reading it on the first call.
+///
+/// Later calls are one atomic load. Threads calling during the first read
+/// wait for it to end.
+#[inline]
+pub fn get() -> &'static Snapshot {
+	match SNAPSHOT.state.load(Ordering::Acquire) {
+		READY => unsafe { SNAPSHOT.get() },
+		_ => SNAPSHOT.init(),
+	}
+}
+
+/// Returns the snapshot of the host, or an error if the host has no CPUID
+/// instruction. `get()` returns an empty snapshot in that case.
+pub fn try_get() -> Result<&'static Snapshot, Error> {
+	match CPUID::available() {
+		true => Ok(get()),
+		_ => Err(Error::CpuidUnavailable),
+	}
+}
+
+/// Returns `true` if the feature is usable on the host.
+///
+/// The check is one relaxed atomic load once the host has been read.
+/// `cpu_has!` checks a feature by name.
+#[inline]
+pub fn has(feature: &Feature) -> bool {
+	let mut detected = DETECTED.load(Ordering::Relaxed);
+
+	if detected == 0 {
+		detected = get().features | CACHED;
+		DETECTED.store(detected, Ordering::Relaxed);
+	}
+
+	((detected >> feature.index()) & 1) == 1
+}
+
+
+
+/// Dump of the host and the flags and model decoded from it.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+	/// CPUID dump.
+	cpuid: CPUID,
+
+	/// Model and flags.
+	target: Target,
+
+	/// Usable features, one bit per entry of the feature table.
+	features: u64,
+}
+
+
+impl Snapshot {
+	/// Reads a snapshot of the host, without caching it.
+	pub fn read() -> Self {
+		Self::from(CPUID::read())
+	}
+
+	/// Decodes a snapshot from a CPUID dump.
+	pub fn from(cpuid: CPUID) -> Self {
+		let target = Target::from(&cpuid);
+
+		let features = Feature::all().iter()
+			.filter(|f| target.has(f))
+			.fold(0u64, |acc, f| acc | (1 << f.index()));
+
+		Snapshot { cpuid, target, features }
+	}
+
+	/// Returns the CPUID dump.
+	pub fn cpuid(&self) -> &CPUID {
+		&self.cpuid
+	}
+
+	/// Returns the model.
+	pub fn model(&self) -> Model {
+		self.target.model()
+	}
+
+	/// Returns the ISA flags.
+	pub fn isa(&self) -> ISAFlags {
+		self.target.isa()
+	}
+
+	/// Returns the SIMD flags.
+	pub fn simd(&self) -> SIMDFlags {
+		self.target.simd()
+	}
+
+	/// Returns the model and flags.
+	pub fn target(&self) -> Target {
+		self.target
+	}
+
+	/// Returns `true` if the feature is usable.
+	pub fn has(&self, feature: &Feature) -> bool {
+		((self.features >> feature.index()) & 1) == 1
+	}
+}
+
+
+
+/// Storage of the process-wide snapshot.
+struct Global {
+	/// `EMPTY`, `BUSY` or `READY`.
+	state: AtomicU8,
+
+	/// Snapshot, initialized once `state` is `READY`.
+	snapshot: UnsafeCell<MaybeUninit<Snapshot>>,
+}
+
+
+// The snapshot is only written by the thread that moves the state to `BUSY`
+// and only read after the state is `READY`.
+unsafe impl Sync for Global {}
+
+
+impl Global {
+	/// Returns the snapshot. The state must be `READY`.
+	unsafe fn get(&self) -> &Snapshot {
+		(*self.snapshot.get()).assume_init_ref()
+	}
+
+	/// Reads the snapshot, or waits for the thread reading it.
+	///
+	/// If the read panics the state goes back to `EMPTY` and the next caller
+	/// reads again, waiting threads retry instead of spinning forever.
+	#[cold]
+	fn init(&self) -> &Snapshot {
+		loop {
+			match self.state.compare_exchange(EMPTY, BUSY, Ordering::Acquire, Ordering::Acquire) {
+				Ok(_) => {
+					let guard = Reset(&self.state);
+
+					unsafe { (*self.snapshot.get()).write(Snapshot::read()); }
+
+					core::mem::forget(guard);
+
+					self.state.store(READY, Ordering::Release);
+
+					break;
+				},
+
+				Err(READY) => break,
+
+				_ => while self.state.load(Ordering::Acquire) == BUSY {
+					core::hint::spin_loop();
+				},
+			}
+		}
+
+		unsafe { self.get() }
+	}
+}
+
+
+
+/// Moves the state back to `EMPTY` when dropped, i.e. on unwind of the read.
+struct Reset<'a>(&'a AtomicU8);
+
+
+impl Drop for Reset<'_> {
+	fn drop(&mut self) {
+		self.0.store(EMPTY, Ordering::Release);
+	}
+}
+
+
+
+/// Returns `true` if the feature with the given rustc or LLVM name is usable
+/// on the host.
+///
+/// Unknown names are a compile error. Features enabled at compile time
+/// (`-C target-feature`) fold to `true`, the others are one relaxed atomic
+/// load of the cached detection.
+///
+/// ```ignore
+/// if cpu_has!("avx512vnni") {
+///     unsafe { dot_vnni(a, b) }
+/// } else {
+///     dot(a, b)
+/// }
+/// ```
+///
+/// The detection agrees with the standard library:
+///
+/// ```
+/// # #[cfg(any(target_arch = "x86", target_arch = "x86_64"))] {
+/// use micro_cpuflags::cpu_has;
+///
+/// // Enabled at compile time on x86-64.
+/// #[cfg(target_arch = "x86_64")]
+/// assert!(cpu_has!("sse2"));
+///
+/// assert_eq!(cpu_has!("sse4.2"),  std::is_x86_feature_detected!("sse4.2"));
+/// assert_eq!(cpu_has!("popcnt"),  std::is_x86_feature_detected!("popcnt"));
+/// assert_eq!(cpu_has!("bmi2"),    std::is_x86_feature_detected!("bmi2"));
+/// assert_eq!(cpu_has!("avx2"),    std::is_x86_feature_detected!("avx2"));
+/// assert_eq!(cpu_has!("fma"),     std::is_x86_feature_detected!("fma"));
+/// assert_eq!(cpu_has!("avx512f"), std::is_x86_feature_detected!("avx512f"));
+/// # }
+/// ```
+///
+/// Unknown names do not compile:
+///
+/// ```compile_fail
+/// let _ = micro_cpuflags::cpu_has!("avx9000");
+/// ```
+#[macro_export]
+macro_rules! cpu_has {
+	($name:literal) => {{
+		const FEATURE: &$crate::feature::Feature = match $crate::feature::Feature::find($name) {
+			Some(feature) => feature,
+			None => panic!(concat!("unknown CPU feature `", $name, "`")),
+		};
+
+		// LLVM only names are not rustc target features.
+		#[allow(unexpected_cfgs)]
+		let enabled = cfg!(target_feature = $name);
+
+		enabled || $crate::cache::has(FEATURE)
+	}};
+}
+
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn reset() {
+		let state = AtomicU8::new(BUSY);
+
+		// A panicking read leaves the state `EMPTY` for the next caller.
+		let result = std::panic::catch_unwind(|| {
+			let _guard = Reset(&state);
+
+			panic!("read failed");
+		});
+
+		assert!(result.is_err());
+		assert_eq!(state.load(Ordering::Acquire), EMPTY);
+
+		// A completed read keeps the state.
+		state.store(BUSY, Ordering::Release);
+		core::mem::forget(Reset(&state));
+
+		assert_eq!(state.load(Ordering::Acquire), BUSY);
+	}
+}
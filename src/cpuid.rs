@@ -0,0 +1,477 @@
+//! Raw CPUID dump.
+//! All the decoders of the crate read from a dump, so their results can be
+//! reproduced on another machine from a saved dump.
+//! On targets other than x86 and x86-64 the host dump is empty.
+
+
+// This Source Code Form is subject to the terms of the
+// Mozilla Public License, v. 2.0. If a copy of the MPL
+// was not distributed with this file, You can obtain one
+// at https://mozilla.org/MPL/2.0/.
+
+
+
+use core::str::FromStr;
+
+use crate::error::Error;
+
+
+#[cfg(target_arch = "x86")]
+use core::arch::x86::{
+	__cpuid_count as cpuidex,
+	_xgetbv       as xgetbv,
+};
+
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::{
+	__cpuid_count as cpuidex,
+	_xgetbv       as xgetbv,
+};
+
+
+
+/// Maximum number of leaves and sub-leaves of a dump.
+const CAPACITY: usize = 160;
+
+/// Highest basic leaf read from the host.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+const MAXLO: u32 = 0x0000003F;
+
+/// Highest extended leaf read from the host.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+const MAXHI: u32 = 0x8000003F;
+
+
+
+/// Registers returned by a CPUID leaf.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Registers {
+	pub eax: u32,
+	pub ebx: u32,
+	pub ecx: u32,
+	pub edx: u32,
+}
+
+
+
+/// Leaf of a dump.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Entry {
+	leaf: u32,
+	subleaf: u32,
+	regs: Registers,
+}
+
+
+impl Entry {
+	const EMPTY: Entry = Entry { leaf: 0, subleaf: 0, regs: Registers { eax: 0, ebx: 0, ecx: 0, edx: 0 } };
+}
+
+
+
+/// CPUID leaves of a processor and its XCR0 register.
+///
+/// The text format is the one of `cpuid -r`, one leaf per line, with an
+/// optional `xcr0=` line:
+///
+/// ```text
+///    0x00000000 0x00: eax=0x0000000d ebx=0x756e6547 ecx=0x6c65746e edx=0x49656e69
+///    0x00000001 0x00: eax=0x000906ea ebx=0x00100800 ecx=0x7ffafbbf edx=0xbfebfbff
+///    xcr0=0x0000000000000007
+/// ```
+#[derive(Debug, Clone)]
+pub struct CPUID {
+	/// Leaves in order of insertion.
+	entries: [Entry; CAPACITY],
+
+	/// Number of leaves.
+	len: usize,
+
+	/// XCR0 register, if known.
+	xcr0: Option<u64>,
+}
+
+
+impl CPUID {
+	/// Creates an empty dump.
+	pub const fn empty() -> Self {
+		CPUID { entries: [Entry::EMPTY; CAPACITY], len: 0, xcr0: None }
+	}
+
+	/// Returns `true` if the host has the CPUID instruction.
+	/// i386 and early i486 CPUs lack it: the EFLAGS ID bit is then read-only.
+	#[cfg(target_arch = "x86")]
+	pub fn available() -> bool {
+		let (before, after): (u32, u32);
+
+		// Toggle the ID bit (21) and check if the change sticks, then restore EFLAGS.
+		unsafe {
+			core::arch::asm!(
+				"pushfd",
+				"pop {0}",
+				"mov {1}, {0}",
+				"xor {1}, 0x200000",
+				"push {1}",
+				"popfd",
+				"pushfd",
+				"pop {1}",
+				"push {0}",
+				"popfd",
+				out(reg) before,
+				out(reg) after,
+			);
+		}
+
+		((before ^ after) & 0x200000) != 0
+	}
+
+	/// Returns `true` if the host has the CPUID instruction.
+	/// Every x86-64 CPU has it.
+	#[cfg(target_arch = "x86_64")]
+	pub fn available() -> bool {
+		true
+	}
+
+	/// Returns `true` if the host has the CPUID instruction.
+	/// Only x86 and x86-64 have it.
+	#[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+	pub fn available() -> bool {
+		false
+	}
+
+	/// Dumps the CPUID leaves of the host, empty if CPUID is not available.
+	/// Sub-leaves are read for leaves 0x7 and 0x17, only sub-leaf 0 for the others.
+	#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+	pub fn read() -> Self {
+		let mut dump = CPUID::empty();
+
+		if !Self::available() { return dump; }
+
+		let lo = query(0x00000000, 0).eax;
+
+		for leaf in 0x00000000..=core::cmp::min(lo, MAXLO) {
+			let regs = query(leaf, 0);
+
+			dump.insert(leaf, 0, regs);
+
+			let subleaves = match leaf {
+				0x07 | 0x17 => core::cmp::min(regs.eax, 3),
+				_ => 0,
+			};
+
+			for subleaf in 1..=subleaves {
+				dump.insert(leaf, subleaf, query(leaf, subleaf));
+			}
+		}
+
+		let hi = query(0x80000000, 0).eax;
+
+		if hi >= 0x80000000 {
+			for leaf in 0x80000000..=core::cmp::min(hi, MAXHI) {
+				dump.insert(leaf, 0, query(leaf, 0));
+			}
+		}
+
+		// OSXSAVE: XGETBV is enabled.
+		if dump.leaf(0x00000001).is_some_and(|info| ((info.ecx >> 27) & 1) == 1) {
+			dump.xcr0 = Some( unsafe { xgetbv(0) } );
+		}
+
+		dump
+	}
+
+	/// Dumps the CPUID leaves of the host.
+	/// Always empty, CPUID is x86 only.
+	#[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+	pub fn read() -> Self {
+		CPUID::empty()
+	}
+
+	/// Dumps the CPUID leaves of the host, or `Error::CpuidUnavailable`.
+	pub fn try_read() -> Result<Self, Error> {
+		match Self::available() {
+			true => Ok(Self::read()),
+			_ => Err(Error::CpuidUnavailable),
+		}
+	}
+
+	/// Inserts or replaces a leaf. Returns `false` if the dump is full.
+	pub fn insert(&mut self, leaf: u32, subleaf: u32, regs: Registers) -> bool {
+		if let Some(entry) = self.entries[..self.len].iter_mut().find(|e| (e.leaf == leaf) && (e.subleaf == subleaf)) {
+			entry.regs = regs;
+			return true;
+		}
+
+		if self.len == CAPACITY { return false; }
+
+		self.entries[self.len] = Entry { leaf, subleaf, regs };
+		self.len += 1;
+
+		true
+	}
+
+	/// Sets the XCR0 register.
+	pub fn set_xcr0(&mut self, xcr0: u64) {
+		self.xcr0 = Some(xcr0);
+	}
+
+	/// Returns the highest basic leaf.
+	pub fn max_leaf(&self) -> u32 {
+		self.find(0x00000000, 0).map_or(0, |regs| regs.eax)
+	}
+
+	/// Returns the highest extended leaf, 0 if there are none.
+	pub fn max_extended(&self) -> u32 {
+		match self.find(0x80000000, 0) {
+			Some(regs) if regs.eax >= 0x80000000 => regs.eax,
+			_ => 0,
+		}
+	}
+
+	/// Returns sub-leaf 0 of the given leaf.
+	pub fn leaf(&self, leaf: u32) -> Option<Registers> {
+		self.subleaf(leaf, 0)
+	}
+
+	/// Returns the given sub-leaf, `None` if it is above the highest leaf or not in the dump.
+	pub fn subleaf(&self, leaf: u32, subleaf: u32) -> Option<Registers> {
+		let max = match leaf {
+			0x80000000..=0xFFFFFFFF => self.max_extended(),
+			_ => self.max_leaf(),
+		};
+
+		if leaf > max { return None; }
+
+		self.find(leaf, subleaf)
+	}
+
+	/// Returns sub-leaf 0 of the given leaf, or `Error::LeafUnsupported`.
+	pub fn try_leaf(&self, leaf: u32) -> Result<Registers, Error> {
+		self.try_subleaf(leaf, 0)
+	}
+
+	/// Returns the given sub-leaf, or `Error::LeafUnsupported` if it is above
+	/// the highest leaf or not in the dump.
+	pub fn try_subleaf(&self, leaf: u32, subleaf: u32) -> Result<Registers, Error> {
+		self.subleaf(leaf, subleaf).ok_or(Error::LeafUnsupported(leaf))
+	}
+
+	/// Returns the XCR0 register.
+	/// When the dump does not record it and OSXSAVE is set, the OS is assumed
+	/// to enable every state component supported (CPUID leaf 0xD).
+	pub fn xcr0(&self) -> u64 {
+		if let Some(xcr0) = self.xcr0 { return xcr0; }
+
+		match (self.leaf(0x00000001), self.leaf(0x0000000D)) {
+			(Some(info), Some(xsave)) if ((info.ecx >> 27) & 1) == 1 => ((xsave.edx as u64) << 32) | (xsave.eax as u64),
+			_ => 0,
+		}
+	}
+
+	/// Iterates over the leaves as `(leaf, subleaf, registers)`.
+	pub fn entries(&self) -> impl Iterator<Item = (u32, u32, Registers)> + '_ {
+		self.entries[..self.len].iter().map(|e| (e.leaf, e.subleaf, e.regs))
+	}
+
+	/// Looks up a leaf without range checks.
+	fn find(&self, leaf: u32, subleaf: u32) -> Option<Registers> {
+		self.entries[..self.len].iter()
+			.find(|e| (e.leaf == leaf) && (e.subleaf == subleaf))
+			.map(|e| e.regs)
+	}
+}
+
+
+
+impl core::fmt::Display for CPUID {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		for (leaf, subleaf, regs) in self.entries() {
+			writeln!(f, "   0x{:08x} 0x{:02x}: eax=0x{:08x} ebx=0x{:08x} ecx=0x{:08x} edx=0x{:08x}", leaf, subleaf, regs.eax, regs.ebx, regs.ecx, regs.edx)?;
+		}
+
+		if let Some(xcr0) = self.xcr0 {
+			writeln!(f, "   xcr0=0x{:016x}", xcr0)?;
+		}
+
+		Ok(())
+	}
+}
+
+
+
+impl FromStr for CPUID {
+	type Err = ParseDumpError;
+
+	/// Parses a dump in the `cpuid -r` format.
+	/// Only the first CPU of a multi CPU dump is read.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut dump = CPUID::empty();
+
+		for line in s.lines().map(str::trim) {
+			if line.is_empty() || line.starts_with('#') { continue; }
+
+			// `CPU N:` headers, stop at the second CPU.
+			if line.starts_with("CPU") {
+				match dump.len {
+					0 => continue,
+					_ => break,
+				}
+			}
+
+			if let Some(value) = line.strip_prefix("xcr0=") {
+				let xcr0 = value.strip_prefix("0x").ok_or(ParseDumpError)?;
+				dump.xcr0 = Some( u64::from_str_radix(xcr0, 16).map_err(|_| ParseDumpError)? );
+				continue;
+			}
+
+			let (index, values) = line.split_once(':').ok_or(ParseDumpError)?;
+
+			let mut index = index.split_whitespace();
+
+			let leaf    = index.next().and_then(hex).ok_or(ParseDumpError)?;
+			let subleaf = index.next().and_then(hex).ok_or(ParseDumpError)?;
+
+			if index.next().is_some() { return Err(ParseDumpError); }
+
+			let mut regs = [None; 4];
+
+			for pair in values.split_whitespace() {
+				let (name, value) = pair.split_once('=').ok_or(ParseDumpError)?;
+
+				let slot = match name {
+					"eax" => &mut regs[0],
+					"ebx" => &mut regs[1],
+					"ecx" => &mut regs[2],
+					"edx" => &mut regs[3],
+					_ => return Err(ParseDumpError),
+				};
+
+				if slot.replace( hex(value).ok_or(ParseDumpError)? ).is_some() { return Err(ParseDumpError) }
+			}
+
+			let regs = match regs {
+				[Some(eax), Some(ebx), Some(ecx), Some(edx)] => Registers { eax, ebx, ecx, edx },
+				_ => return Err(ParseDumpError),
+			};
+
+			if !dump.insert(leaf, subleaf, regs) { return Err(ParseDumpError) }
+		}
+
+		Ok(dump)
+	}
+}
+
+
+
+/// Error returned when parsing a `CPUID` dump fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseDumpError;
+
+
+impl core::fmt::Display for ParseDumpError {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		f.write_str("invalid CPUID dump")
+	}
+}
+
+
+
+/// Executes CPUID on the host.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn query(leaf: u32, subleaf: u32) -> Registers {
+	let r = unsafe { cpuidex(leaf, subleaf) };
+
+	Registers { eax: r.eax, ebx: r.ebx, ecx: r.ecx, edx: r.edx }
+}
+
+/// Parses a `0x` prefixed hexadecimal value.
+fn hex(s: &str) -> Option<u32> {
+	u32::from_str_radix(s.strip_prefix("0x")?, 16).ok()
+}
+
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::format;
+
+	const DUMP: &str = "
+		CPU 0:
+		   0x00000000 0x00: eax=0x00000016 ebx=0x756e6547 ecx=0x6c65746e edx=0x49656e69
+		   0x00000001 0x00: eax=0x000906ea ebx=0x00100800 ecx=0x7ffafbbf edx=0xbfebfbff
+		   0x00000007 0x00: eax=0x00000000 ebx=0x029c6fbf ecx=0x40000000 edx=0xbc000400
+		   0x00000017 0x00: eax=0x00000000 ebx=0x00000000 ecx=0x00000000 edx=0x00000000
+		   0x80000000 0x00: eax=0x80000008 ebx=0x00000000 ecx=0x00000000 edx=0x00000000
+		   0x80000008 0x00: eax=0x00003027 ebx=0x00000000 ecx=0x00000000 edx=0x00000000
+		   xcr0=0x000000000000001f
+		CPU 1:
+		   0x00000000 0x00: eax=0x00000000 ebx=0x00000000 ecx=0x00000000 edx=0x00000000
+	";
+
+	#[test]
+	fn parse() {
+		let cpuid: CPUID = DUMP.parse().unwrap();
+
+		assert_eq!(cpuid.entries().count(), 6);
+		assert_eq!(cpuid.max_leaf(), 0x16);
+		assert_eq!(cpuid.max_extended(), 0x80000008);
+		assert_eq!(cpuid.xcr0(), 0x1F);
+
+		assert_eq!(cpuid.leaf(0x01).map(|regs| regs.eax), Some(0x000906EA));
+		assert_eq!(cpuid.subleaf(0x07, 0).map(|regs| regs.ebx), Some(0x029C6FBF));
+		assert_eq!(cpuid.leaf(0x80000008).map(|regs| regs.eax), Some(0x3027));
+	}
+
+	#[test]
+	fn range() {
+		let mut cpuid: CPUID = DUMP.parse().unwrap();
+
+		// Leaves above the highest leaf are not reported, even if in the dump.
+		cpuid.insert(0x00, 0, Registers { eax: 0x07, ..Registers::default() });
+
+		assert!(cpuid.leaf(0x07).is_some());
+		assert_eq!(cpuid.leaf(0x17), None);
+		assert_eq!(cpuid.try_leaf(0x17), Err(Error::LeafUnsupported(0x17)));
+
+		// In range, but not in the dump.
+		assert_eq!(cpuid.subleaf(0x07, 1), None);
+		assert_eq!(cpuid.leaf(0x80000009), None);
+
+		assert_eq!(CPUID::empty().leaf(0x00), None);
+		assert_eq!(CPUID::empty().max_extended(), 0);
+	}
+
+	#[test]
+	fn roundtrip() {
+		let cpuid: CPUID = DUMP.parse().unwrap();
+		let again: CPUID = format!("{}", cpuid).parse().unwrap();
+
+		assert!(cpuid.entries().eq(again.entries()));
+		assert_eq!(again.xcr0(), 0x1F);
+	}
+
+	#[test]
+	fn invalid() {
+		const LEAF: &str = "0x00000000 0x00:";
+
+		assert!(format!("{} eax=0x1 ebx=0x2 ecx=0x3 edx=0x4", LEAF).parse::<CPUID>().is_ok());
+
+		let invalid = [
+			"eax=0x1 ebx=0x2 ecx=0x3",
+			"eax=0x1 eax=0x1 ebx=0x2 ecx=0x3 edx=0x4",
+			"eax=0x1 ebx=0x2 ecx=0x3 esi=0x4",
+			"eax=1 ebx=0x2 ecx=0x3 edx=0x4",
+			"eax=0x1 ebx=0x2 ecx=0x3 edx=0xG",
+		];
+
+		for regs in invalid {
+			assert_eq!(format!("{} {}", LEAF, regs).parse::<CPUID>().err(), Some(ParseDumpError), "{}", regs);
+		}
+
+		assert_eq!("0x00000000: eax=0x1 ebx=0x2 ecx=0x3 edx=0x4".parse::<CPUID>().err(), Some(ParseDumpError));
+		assert_eq!("0x00000000 0x00 0x00: eax=0x1 ebx=0x2 ecx=0x3 edx=0x4".parse::<CPUID>().err(), Some(ParseDumpError));
+		assert_eq!("xcr0=7".parse::<CPUID>().err(), Some(ParseDumpError));
+	}
+}
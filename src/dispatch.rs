@@ -0,0 +1,230 @@
+//! Runtime function multiversioning.
+//! Selects the best variant of a function for the host once and caches it.
+
+
+// This Source Code Form is subject to the terms of the
+// Mozilla Public License, v. 2.0. If a copy of the MPL
+// was not distributed with this file, You can obtain one
+// at https://mozilla.org/MPL/2.0/.
+
+
+
+use core::sync::atomic::{ AtomicPtr, Ordering };
+
+use crate::feature::Feature;
+
+
+
+/// Variant of a function: names of the features it needs and the function.
+pub type Variant<F> = (&'static [&'static str], F);
+
+
+
+/// Function with several variants, each compiled for a set of features.
+///
+/// Variants are given from the best to the worst. On the first call the
+/// first variant whose features are all usable is selected, later calls
+/// are one atomic load and one indirect call. Features are named as in
+/// rustc or LLVM, unknown names are never usable. The last variant should
+/// need no feature.
+///
+/// ```ignore
+/// static SUM: Dispatcher<unsafe fn(&[u8]) -> u32> = Dispatcher::new(&[
+///     (&["avx512f", "avx512bw"], sum_avx512),
+///     (&["avx2"],                sum_avx2),
+///     (&[],                      sum),
+/// ]);
+///
+/// // Safe: the selected variant only needs usable features.
+/// let total = unsafe { SUM.get()(data) };
+/// ```
+pub struct Dispatcher<F: 'static> {
+	/// Variants, from the best to the worst.
+	variants: &'static [Variant<F>],
+
+	/// Selected variant, null until selected.
+	selected: AtomicPtr<Variant<F>>,
+}
+
+
+impl<F: Copy> Dispatcher<F> {
+	/// Creates a dispatcher of the given variants.
+	pub const fn new(variants: &'static [Variant<F>]) -> Self {
+		Dispatcher { variants, selected: AtomicPtr::new(core::ptr::null_mut()) }
+	}
+
+	/// Returns the selected function, selecting it on the first call.
+	///
+	/// # Panics
+	/// Panics if no variant is usable on the host.
+	#[inline]
+	pub fn get(&self) -> F {
+		self.variant().1
+	}
+
+	/// Returns the index of the selected variant, selecting it on the first call.
+	///
+	/// # Panics
+	/// Panics if no variant is usable on the host.
+	pub fn selected(&self) -> usize {
+		let variant: *const Variant<F> = self.variant();
+
+		unsafe { variant.offset_from(self.variants.as_ptr()) as usize }
+	}
+
+	/// Returns the variants.
+	pub fn variants(&self) -> &'static [Variant<F>] {
+		self.variants
+	}
+
+	/// Forces the given variant, e.g. to test every variant on one machine.
+	/// Calling a variant whose features are not usable is undefined behavior.
+	///
+	/// # Panics
+	/// Panics if the index is out of range.
+	pub fn force(&self, index: usize) {
+		let variant = &self.variants[index];
+
+		self.selected.store(variant as *const Variant<F> as *mut Variant<F>, Ordering::Relaxed);
+	}
+
+	/// Clears the selected variant, the next call selects again.
+	pub fn reset(&self) {
+		self.selected.store(core::ptr::null_mut(), Ordering::Relaxed);
+	}
+
+	/// Returns the selected variant, selecting it on the first call.
+	#[inline]
+	fn variant(&self) -> &'static Variant<F> {
+		let selected = self.selected.load(Ordering::Relaxed);
+
+		// The pointer is either null or points into the `'static` variants.
+		match unsafe { selected.as_ref() } {
+			Some(variant) => variant,
+			_ => self.select(),
+		}
+	}
+
+	/// Selects the best usable variant.
+	#[cold]
+	fn select(&self) -> &'static Variant<F> {
+		let usable = |name: &&str| Feature::find(name).is_some_and(crate::cache::has);
+
+		let variant = self.variants.iter()
+			.find(|(features, _)| features.iter().all(usable))
+			.expect("no variant of the function is usable on this CPU");
+
+		self.selected.store(variant as *const Variant<F> as *mut Variant<F>, Ordering::Relaxed);
+
+		variant
+	}
+}
+
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::boxed::Box;
+	use std::vec::Vec;
+
+	type Test = fn() -> usize;
+
+	fn first() -> usize { 0 }
+	fn second() -> usize { 1 }
+	fn third() -> usize { 2 }
+
+	/// Name of a feature usable on the host, if any.
+	fn usable() -> Option<&'static str> {
+		Feature::all().iter().find(|f| crate::cache::has(f)).map(|f| f.llvm())
+	}
+
+	/// Name of a feature not usable on the host, if any.
+	fn unusable() -> Option<&'static str> {
+		Feature::all().iter().find(|f| !crate::cache::has(f)).map(|f| f.llvm())
+	}
+
+	/// Leaks the variants so that they outlive the dispatcher.
+	fn variants(list: &[(Option<&'static str>, Test)]) -> &'static [Variant<Test>] {
+		let list: Vec<_> = list.iter()
+			.map(|&(name, f)| (&*Box::leak(name.into_iter().collect::<Box<[_]>>()), f))
+			.collect();
+
+		Box::leak(list.into_boxed_slice())
+	}
+
+	#[test]
+	fn select() {
+		// Unknown names are never usable.
+		let dispatcher = Dispatcher::new(variants(&[
+			(Some("no-such-feature"), first as Test),
+			(None, second),
+		]));
+
+		assert_eq!(dispatcher.selected(), 1);
+		assert_eq!(dispatcher.get()(), 1);
+
+		// The first usable variant wins over later usable ones.
+		if let Some(name) = usable() {
+			let dispatcher = Dispatcher::new(variants(&[
+				(Some(name), first as Test),
+				(None, second),
+			]));
+
+			assert_eq!(dispatcher.selected(), 0);
+		}
+
+		// Known features the host lacks are skipped.
+		if let Some(name) = unusable() {
+			let dispatcher = Dispatcher::new(variants(&[
+				(Some(name), first as Test),
+				(usable(), second),
+				(None, third),
+			]));
+
+			assert_eq!(dispatcher.selected(), 1);
+		}
+	}
+
+	#[test]
+	fn force() {
+		let dispatcher = Dispatcher::new(variants(&[
+			(Some("no-such-feature"), first as Test),
+			(None, second),
+			(None, third),
+		]));
+
+		dispatcher.force(2);
+		assert_eq!(dispatcher.selected(), 2);
+		assert_eq!(dispatcher.get()(), 2);
+
+		// Forcing skips the feature check.
+		dispatcher.force(0);
+		assert_eq!(dispatcher.selected(), 0);
+		assert_eq!(dispatcher.get()(), 0);
+
+		// The next call selects again.
+		dispatcher.reset();
+		assert_eq!(dispatcher.selected(), 1);
+		assert_eq!(dispatcher.variants().len(), 3);
+	}
+
+	#[test]
+	#[should_panic(expected = "no variant of the function is usable")]
+	fn unusable_only() {
+		let dispatcher = Dispatcher::new(variants(&[
+			(Some("no-such-feature"), first as Test),
+		]));
+
+		dispatcher.get();
+	}
+
+	#[test]
+	#[should_panic]
+	fn force_out_of_range() {
+		let dispatcher = Dispatcher::new(variants(&[(None, first as Test)]));
+
+		dispatcher.force(1);
+	}
+}
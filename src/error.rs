@@ -0,0 +1,45 @@
+//! Errors of the fallible readers.
+//! Tells apart what the CPU does not report from what the crate does not know.
+
+
+// This Source Code Form is subject to the terms of the
+// Mozilla Public License, v. 2.0. If a copy of the MPL
+// was not distributed with this file, You can obtain one
+// at https://mozilla.org/MPL/2.0/.
+
+
+
+/// Error returned by the `try_*` readers.
+///
+/// A model the crate does not know is not an error, it decodes to
+/// `CPUModel::Unknown`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Error {
+	/// The host has no CPUID instruction.
+	CpuidUnavailable,
+
+	/// The CPU does not report the leaf, or the dump does not contain it.
+	LeafUnsupported(u32),
+
+	/// A brand string is not valid UTF-8.
+	InvalidBrand,
+
+	/// The CPU reports the leaf but its values contradict each other.
+	InconsistentData,
+}
+
+
+impl core::fmt::Display for Error {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		match *self {
+			Error::CpuidUnavailable      => f.write_str("CPUID is not available on this processor"),
+			Error::LeafUnsupported(leaf) => write!(f, "CPUID leaf 0x{:08X} is not supported", leaf),
+			Error::InvalidBrand          => f.write_str("brand string is not valid UTF-8"),
+			Error::InconsistentData      => f.write_str("inconsistent CPUID data"),
+		}
+	}
+}
+
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
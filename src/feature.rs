@@ -0,0 +1,155 @@
+//! Feature table.
+//! Maps the flags of the crate to their rustc, GCC and LLVM names.
+//! The table is generated from `data/features.txt` by the build script.
+
+
+// This Source Code Form is subject to the terms of the
+// Mozilla Public License, v. 2.0. If a copy of the MPL
+// was not distributed with this file, You can obtain one
+// at https://mozilla.org/MPL/2.0/.
+
+
+
+use crate::isa::ISAFlags;
+use crate::simd::SIMDFlags;
+
+
+include!(concat!(env!("OUT_DIR"), "/features.rs"));
+
+
+
+/// Entry of the feature table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Feature {
+	/// Position in the table.
+	index: u8,
+
+	/// Flag set of the flag.
+	set: FeatureSet,
+
+	/// Flag of the feature.
+	flag: u32,
+
+	/// `SIMDFlags` flag of the OS support needed, if any.
+	requires: Option<u32>,
+
+	/// rustc name, if rustc knows the feature.
+	rustc: Option<&'static str>,
+
+	/// GCC and Clang `-m` option name, if there is one.
+	gcc: Option<&'static str>,
+
+	/// LLVM name.
+	llvm: &'static str,
+}
+
+
+impl Feature {
+	/// Returns the feature table.
+	pub fn all() -> &'static [Feature] {
+		FEATURES
+	}
+
+	/// Finds a feature by its rustc or LLVM name.
+	/// Usable in constants, so unknown names can be rejected at compile time.
+	pub const fn find(name: &str) -> Option<&'static Feature> {
+		let mut i = 0;
+
+		while i < FEATURES.len() {
+			let f = &FEATURES[i];
+
+			let rustc = match f.rustc {
+				Some(rustc) => equal(rustc, name),
+				None => false,
+			};
+
+			if rustc || equal(f.llvm, name) { return Some(f); }
+
+			i += 1;
+		}
+
+		None
+	}
+
+	/// Returns the position of the feature in the table.
+	pub fn index(&self) -> usize {
+		self.index as usize
+	}
+
+	/// Returns the flag set of the flag.
+	pub fn set(&self) -> FeatureSet {
+		self.set
+	}
+
+	/// Returns the flag of the feature.
+	pub fn flag(&self) -> u32 {
+		self.flag
+	}
+
+	/// Returns the rustc name, `None` if rustc does not know the feature.
+	pub fn rustc(&self) -> Option<&'static str> {
+		self.rustc
+	}
+
+	/// Returns the GCC and Clang `-m` option name, `None` if there is none.
+	pub fn gcc(&self) -> Option<&'static str> {
+		self.gcc
+	}
+
+	/// Returns the LLVM name.
+	pub fn llvm(&self) -> &'static str {
+		self.llvm
+	}
+
+	/// Returns `true` if the feature is present and usable with the given flags.
+	/// Vector features also need the OS to save the vector state.
+	pub fn detected(&self, isa: &ISAFlags, simd: &SIMDFlags) -> bool {
+		let present = match self.set {
+			FeatureSet::ISA    => isa.isa(self.flag),
+			FeatureSet::SIMD   => simd.simd(self.flag),
+			FeatureSet::AVX512 => simd.avx512(self.flag),
+		};
+
+		present && self.requires.map_or(true, |f| simd.simd(f))
+	}
+}
+
+
+impl core::fmt::Display for Feature {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		f.write_str(self.rustc.unwrap_or(self.llvm))
+	}
+}
+
+
+
+/// Flag set of a feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FeatureSet {
+	/// `ISAFlags`.
+	ISA,
+
+	/// `SIMDFlags` SIMD flags.
+	SIMD,
+
+	/// `SIMDFlags` AVX-512 extension flags.
+	AVX512,
+}
+
+
+
+/// Compares two strings in a constant.
+const fn equal(a: &str, b: &str) -> bool {
+	let (a, b) = (a.as_bytes(), b.as_bytes());
+
+	if a.len() != b.len() { return false; }
+
+	let mut i = 0;
+
+	while i < a.len() {
+		if a[i] != b[i] { return false; }
+		i += 1;
+	}
+
+	true
+}
@@ -0,0 +1,179 @@
+//! Brand strings of the processor.
+//! Stored inline, so reading them needs no allocator.
+
+
+// This Source Code Form is subject to the terms of the
+// Mozilla Public License, v. 2.0. If a copy of the MPL
+// was not distributed with this file, You can obtain one
+// at https://mozilla.org/MPL/2.0/.
+
+
+
+use crate::cpuid::CPUID;
+use crate::error::Error;
+
+
+
+/// Maximum length of a brand string: 3 leaves of 16 bytes.
+const CAPACITY: usize = 48;
+
+
+
+/// Brand string read from CPUID, up to 48 bytes.
+/// The bytes are not checked to be UTF-8, dumps may come from anywhere.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Brand {
+	/// Raw bytes, zero after `len`.
+	bytes: [u8; CAPACITY],
+
+	/// Number of bytes.
+	len: u8,
+}
+
+
+impl Brand {
+	/// Creates an empty brand.
+	pub const fn empty() -> Self {
+		Brand { bytes: [0; CAPACITY], len: 0 }
+	}
+
+	/// Creates a brand from the registers of its leaves, up to the first NUL.
+	pub fn from_registers(registers: &[u32; 12]) -> Self {
+		let mut brand = Brand::empty();
+
+		for (i, register) in registers.iter().enumerate() {
+			brand.bytes[i * 4..(i + 1) * 4].copy_from_slice(&register.to_le_bytes());
+		}
+
+		brand.len = brand.bytes.iter().position(|c| *c == 0).unwrap_or(CAPACITY) as u8;
+		brand.bytes[brand.len as usize..].fill(0);
+
+		brand
+	}
+
+	/// Reads the product brand from CPUID leaves 0x80000002 to 0x80000004.
+	/// Empty if the dump does not report it.
+	pub fn product(cpuid: &CPUID) -> Self {
+		if cpuid.max_extended() < 0x80000004 { return Brand::empty(); }
+
+		let mut rawstr = [0u32; 12];
+
+		for i in 0..3 {
+			let raw = cpuid.leaf(0x80000002 + i).unwrap_or_default();
+
+			rawstr[(i * 4    ) as usize] = raw.eax;
+			rawstr[(i * 4 + 1) as usize] = raw.ebx;
+			rawstr[(i * 4 + 2) as usize] = raw.ecx;
+			rawstr[(i * 4 + 3) as usize] = raw.edx;
+		}
+
+		Brand::from_registers(&rawstr)
+	}
+
+	/// Returns the raw bytes.
+	pub fn as_bytes(&self) -> &[u8] {
+		&self.bytes[..self.len as usize]
+	}
+
+	/// Returns the brand, or `Error::InvalidBrand` if it is not valid UTF-8.
+	pub fn to_str(&self) -> Result<&str, Error> {
+		core::str::from_utf8(self.as_bytes()).map_err(|_| Error::InvalidBrand)
+	}
+
+	/// Returns `true` if the brand is empty.
+	pub fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+}
+
+
+impl Default for Brand {
+	fn default() -> Self {
+		Brand::empty()
+	}
+}
+
+
+/// Invalid UTF-8 sequences are written as U+FFFD.
+impl core::fmt::Display for Brand {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		let mut bytes = self.as_bytes();
+
+		loop {
+			let error = match core::str::from_utf8(bytes) {
+				Ok(valid) => return f.write_str(valid),
+				Err(error) => error,
+			};
+
+			let (valid, invalid) = bytes.split_at(error.valid_up_to());
+
+			f.write_str(core::str::from_utf8(valid).unwrap_or_default())?;
+			f.write_str("\u{FFFD}")?;
+
+			// An incomplete sequence at the end has no length.
+			bytes = &invalid[error.error_len().unwrap_or(invalid.len())..];
+		}
+	}
+}
+
+
+impl core::fmt::Debug for Brand {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		match self.to_str() {
+			Ok(s) => core::fmt::Debug::fmt(s, f),
+			_ => core::fmt::Debug::fmt(self.as_bytes(), f),
+		}
+	}
+}
+
+
+#[cfg(feature = "alloc")]
+impl From<Brand> for alloc::string::String {
+	/// Invalid UTF-8 sequences are replaced by U+FFFD.
+	fn from(brand: Brand) -> Self {
+		alloc::string::String::from_utf8_lossy(brand.as_bytes()).into_owned()
+	}
+}
+
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::{ format, string::String };
+
+	/// Packs a string in the registers of the brand leaves.
+	fn registers(bytes: &[u8]) -> [u32; 12] {
+		let mut raw = [0u8; CAPACITY];
+		raw[..bytes.len()].copy_from_slice(bytes);
+
+		let mut registers = [0u32; 12];
+
+		for (register, chunk) in registers.iter_mut().zip(raw.chunks(4)) {
+			*register = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+		}
+
+		registers
+	}
+
+	#[test]
+	fn from_registers() {
+		let brand = Brand::from_registers(&registers(b"Intel(R) Core(TM) i5-8259U CPU @ 2.30GHz"));
+
+		assert_eq!(brand.to_str(), Ok("Intel(R) Core(TM) i5-8259U CPU @ 2.30GHz"));
+		assert_eq!(format!("{}", brand), "Intel(R) Core(TM) i5-8259U CPU @ 2.30GHz");
+
+		assert!(Brand::from_registers(&[0; 12]).is_empty());
+	}
+
+	#[test]
+	fn invalid() {
+		for bytes in [&b"AMD \xFF Ryzen"[..], b"\xC3", b"a\xE2\x82b\xF0\x9F\x98", b"\xED\xA0\x80z"] {
+			let brand = Brand::from_registers(&registers(bytes));
+
+			assert_eq!(brand.to_str(), Err(Error::InvalidBrand));
+			assert_eq!(format!("{}", brand), String::from_utf8_lossy(bytes), "{:?}", bytes);
+		}
+	}
+}
@@ -0,0 +1,243 @@
+//! Known errata of the processor.
+//! The errata table is generated from `data/errata.txt` by the build script.
+
+
+// This Source Code Form is subject to the terms of the
+// Mozilla Public License, v. 2.0. If a copy of the MPL
+// was not distributed with this file, You can obtain one
+// at https://mozilla.org/MPL/2.0/.
+
+
+
+use super::{ CPUVendor, Model };
+
+
+include!(concat!(env!("OUT_DIR"), "/errata.rs"));
+
+
+
+/// Entry of the errata database.
+#[derive(Debug, Clone, Copy)]
+pub struct Erratum {
+	/// Identifier of the erratum.
+	id: &'static str,
+
+	/// Vendor of the CPU.
+	vendor: CPUVendor,
+
+	/// Decoded family.
+	family: u16,
+
+	/// Inclusive range of decoded models.
+	model: (u8, u8),
+
+	/// Inclusive range of steppings. `None` matches any stepping.
+	stepping: Option<(u8, u8)>,
+
+	/// Inclusive range of affected microcode revisions. `None` matches any revision.
+	microcode: Option<(u32, u32)>,
+
+	/// Impact of the erratum.
+	impact: Impact,
+
+	/// Description of the erratum.
+	title: &'static str,
+
+	/// Suggested workaround.
+	workaround: &'static str,
+}
+
+
+impl Erratum {
+	/// Returns the errata database.
+	pub fn all() -> &'static [Erratum] {
+		&ERRATA
+	}
+
+	/// Returns the errata matching the given model and microcode revision.
+	pub fn find(model: Model, microcode: Option<u32>) -> impl Iterator<Item = &'static Erratum> {
+		ERRATA.iter().filter(move |erratum| erratum.matches(&model, microcode))
+	}
+
+	/// Returns the identifier.
+	pub fn id(&self) -> &'static str {
+		self.id
+	}
+
+	/// Returns the vendor.
+	pub fn vendor(&self) -> CPUVendor {
+		self.vendor
+	}
+
+	/// Returns the decoded family.
+	pub fn family(&self) -> u16 {
+		self.family
+	}
+
+	/// Returns the inclusive range of decoded models.
+	pub fn models(&self) -> (u8, u8) {
+		self.model
+	}
+
+	/// Returns the inclusive range of steppings, `None` if any stepping matches.
+	pub fn steppings(&self) -> Option<(u8, u8)> {
+		self.stepping
+	}
+
+	/// Returns the inclusive range of affected microcode revisions, `None` if any revision matches.
+	pub fn microcode(&self) -> Option<(u32, u32)> {
+		self.microcode
+	}
+
+	/// Returns the impact.
+	pub fn impact(&self) -> Impact {
+		self.impact
+	}
+
+	/// Returns the description.
+	pub fn title(&self) -> &'static str {
+		self.title
+	}
+
+	/// Returns the suggested workaround.
+	pub fn workaround(&self) -> &'static str {
+		self.workaround
+	}
+
+	/// Returns `true` if the erratum affects the given model and microcode revision.
+	/// An unknown microcode revision matches, as the erratum may apply.
+	pub fn matches(&self, model: &Model, microcode: Option<u32>) -> bool {
+		let signature = model.signature();
+
+		let number = signature.model();
+
+		let stepping = match (self.stepping, signature.stepping()) {
+			(None, _) => true,
+			(Some((lo, hi)), Some(s)) => (lo <= s) && (s <= hi),
+			(Some(_), None) => false,
+		};
+
+		let microcode = match (self.microcode, microcode) {
+			(Some((lo, hi)), Some(rev)) => (lo <= rev) && (rev <= hi),
+			_ => true,
+		};
+
+		(self.vendor == model.vendor()) && (self.family == signature.family()) && (self.model.0 <= number) && (number <= self.model.1) && stepping && microcode
+	}
+}
+
+
+
+impl core::fmt::Display for Erratum {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		write!(f, "[{}] {}\n  Impact: {}\n  Workaround: {}", self.id, self.title, self.impact, self.workaround)
+	}
+}
+
+
+
+/// Impact of an erratum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Impact {
+	/// Instructions return wrong results.
+	Correctness,
+
+	/// Data may leak across security boundaries.
+	Security,
+
+	/// The system may become unstable or degrade.
+	Stability,
+
+	/// Code runs slower than expected.
+	Performance,
+
+	/// A feature is disabled or unusable.
+	Feature,
+}
+
+
+impl core::fmt::Display for Impact {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		let args = match *self {
+			Impact::Correctness => "Correctness",
+			Impact::Security    => "Security",
+			Impact::Stability   => "Stability",
+			Impact::Performance => "Performance",
+			Impact::Feature     => "Feature",
+		};
+
+		f.write_str(args)
+	}
+}
+
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use crate::cpuid::{ CPUID, Registers };
+
+	/// Builds the model of a dump with the given vendor string and signature.
+	fn model(vendor: (u32, u32, u32), eax: u32) -> Model {
+		let (ebx, edx, ecx) = vendor;
+
+		let mut cpuid = CPUID::empty();
+
+		cpuid.insert(0x00, 0, Registers { eax: 0x01, ebx, ecx, edx });
+		cpuid.insert(0x01, 0, Registers { eax, ..Registers::default() });
+
+		Model::from(&cpuid)
+	}
+
+	const INTEL: (u32, u32, u32) = (0x756E6547, 0x49656E69, 0x6C65746E);
+	const AMD:   (u32, u32, u32) = (0x68747541, 0x69746E65, 0x444D4163);
+
+	#[test]
+	fn table() {
+		for erratum in Erratum::all() {
+			let (lo, hi) = erratum.models();
+			assert!(lo <= hi, "{}", erratum.id());
+
+			if let Some((lo, hi)) = erratum.steppings() {
+				assert!(lo <= hi, "{}", erratum.id());
+			}
+
+			if let Some((lo, hi)) = erratum.microcode() {
+				assert!(lo <= hi, "{}", erratum.id());
+			}
+		}
+	}
+
+	#[test]
+	fn microcode() {
+		// Matisse, family 0x17 model 0x71.
+		let matisse = model(AMD, 0x00870F10);
+
+		let zenbleed = |rev| Erratum::find(matisse, rev).any(|e| e.id() == "zenbleed");
+
+		assert!(zenbleed(Some(0x08701030)));
+		assert!(zenbleed(Some(0x08701031)));
+		assert!(!zenbleed(Some(0x08701032)));
+
+		// An unknown revision may be affected.
+		assert!(zenbleed(None));
+	}
+
+	#[test]
+	fn signature() {
+		// Coffee Lake, family 0x06 model 0x9E.
+		let coffeelake = model(INTEL, 0x000906EA);
+
+		assert!(Erratum::find(coffeelake, None).any(|e| e.id() == "skylake-jcc"));
+		assert!(!Erratum::find(coffeelake, None).any(|e| e.id() == "haswell-tsx"));
+
+		// Same family and model, other vendor.
+		let amd = model(AMD, 0x000906EA);
+
+		assert!(!Erratum::find(amd, None).any(|e| e.vendor() == CPUVendor::Intel));
+
+		// Unknown vendor: no errata.
+		assert_eq!(Erratum::find(Model::empty(), None).count(), 0);
+	}
+}
@@ -0,0 +1,398 @@
+//! Microcode revision of the processor.
+//! Read from the operating system, CPUID does not report it.
+
+
+// This Source Code Form is subject to the terms of the
+// Mozilla Public License, v. 2.0. If a copy of the MPL
+// was not distributed with this file, You can obtain one
+// at https://mozilla.org/MPL/2.0/.
+
+
+
+use super::{ CPUVendor, Model, Signature };
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Microcode {
+	/// Microcode revision.
+	revision: u32,
+
+	/// Source of the revision.
+	source: Source,
+}
+
+
+impl Microcode {
+	/// Creates a microcode revision read from the given source.
+	pub const fn new(revision: u32, source: Source) -> Self {
+		Microcode { revision, source }
+	}
+
+	/// Returns the revision.
+	pub fn revision(&self) -> u32 {
+		self.revision
+	}
+
+	/// Returns the source of the revision.
+	pub fn source(&self) -> Source {
+		self.source
+	}
+
+	/// Reads the microcode revision of the first CPU.
+	pub fn read() -> Option<Microcode> {
+		Self::read_cpu(0)
+	}
+
+	/// Reads the microcode revision of the given CPU from sysfs,
+	/// falling back to `/proc/cpuinfo`.
+	#[cfg(feature = "std")]
+	pub fn read_cpu(cpu: usize) -> Option<Microcode> {
+		Self::sysfs(cpu).or_else(|| Self::cpuinfo(cpu))
+	}
+
+	/// Reads the microcode revision of the given CPU.
+	/// Not available without the `std` feature.
+	#[cfg(not(feature = "std"))]
+	pub fn read_cpu(_: usize) -> Option<Microcode> {
+		None
+	}
+
+	/// Reads the microcode revision of the given CPU from MSR 0x8B
+	/// through `/dev/cpu/N/msr`. Needs the `msr` module and root privileges.
+	/// Intel reports the revision in the high half of the MSR, AMD in the low half.
+	/// Intel only updates the MSR when CPUID leaf 1 runs after a write of 0, so it
+	/// may hold a stale revision or 0: see `read_msr_refreshed`.
+	#[cfg(all(feature = "std", target_os = "linux"))]
+	pub fn read_msr(cpu: usize, vendor: CPUVendor) -> Option<Microcode> {
+		use std::{ fs::File, os::unix::fs::FileExt };
+
+		let file = File::open(std::format!("/dev/cpu/{}/msr", cpu)).ok()?;
+
+		let mut raw = [0u8; 8];
+		file.read_exact_at(&mut raw, 0x8B).ok()?;
+
+		let msr = u64::from_le_bytes(raw);
+
+		let revision = match vendor {
+			CPUVendor::AMD | CPUVendor::Hygon => msr as u32,
+			_ => (msr >> 32) as u32,
+		};
+
+		Some( Microcode::new(revision, Source::MSR) )
+	}
+
+	/// Reads the microcode revision of the given CPU from MSR 0x8B.
+	/// Not available on this operating system or without the `std` feature.
+	#[cfg(not(all(feature = "std", target_os = "linux")))]
+	pub fn read_msr(_: usize, _: CPUVendor) -> Option<Microcode> {
+		None
+	}
+
+	/// Reads the microcode revision of the given Intel CPU from MSR 0x8B after
+	/// the sequence of the Intel SDM: the MSR is cleared, then CPUID leaf 1 runs
+	/// on that CPU through `/dev/cpu/N/cpuid`. Needs the `msr` and `cpuid` modules
+	/// and root privileges. Linux logs the write and taints the kernel, prefer
+	/// `read_cpu`, which reads the revision the kernel keeps.
+	/// `None` for the other vendors or if the MSR still reads 0.
+	#[cfg(all(feature = "std", target_os = "linux"))]
+	pub fn read_msr_refreshed(cpu: usize, vendor: CPUVendor) -> Option<Microcode> {
+		use std::{ fs::{ File, OpenOptions }, os::unix::fs::FileExt };
+
+		if vendor != CPUVendor::Intel { return None; }
+
+		// Open both devices before the MSR is cleared.
+		let cpuid = File::open(std::format!("/dev/cpu/{}/cpuid", cpu)).ok()?;
+		let msr = OpenOptions::new().read(true).write(true).open(std::format!("/dev/cpu/{}/msr", cpu)).ok()?;
+
+		msr.write_all_at(&[0u8; 8], 0x8B).ok()?;
+
+		// The offset is the leaf, CPUID runs on the CPU of the device.
+		cpuid.read_exact_at(&mut [0u8; 16], 0x01).ok()?;
+
+		let mut raw = [0u8; 8];
+		msr.read_exact_at(&mut raw, 0x8B).ok()?;
+
+		match (u64::from_le_bytes(raw) >> 32) as u32 {
+			0 => None,
+			revision => Some( Microcode::new(revision, Source::MSR) ),
+		}
+	}
+
+	/// Reads the microcode revision of the given Intel CPU from MSR 0x8B.
+	/// Not available on this operating system or without the `std` feature.
+	#[cfg(not(all(feature = "std", target_os = "linux")))]
+	pub fn read_msr_refreshed(_: usize, _: CPUVendor) -> Option<Microcode> {
+		None
+	}
+
+	/// Reads `/sys/devices/system/cpu/cpuN/microcode/version`.
+	#[cfg(feature = "std")]
+	fn sysfs(cpu: usize) -> Option<Microcode> {
+		let raw = std::fs::read_to_string(std::format!("/sys/devices/system/cpu/cpu{}/microcode/version", cpu)).ok()?;
+
+		Some( Microcode::new(parse(&raw)?, Source::Sysfs) )
+	}
+
+	/// Reads the `microcode` field of the given CPU in `/proc/cpuinfo`.
+	#[cfg(feature = "std")]
+	fn cpuinfo(cpu: usize) -> Option<Microcode> {
+		let raw = std::fs::read_to_string("/proc/cpuinfo").ok()?;
+
+		let revision = raw.split("\n\n")
+			.filter(|block| !block.trim().is_empty())
+			.nth(cpu)?
+			.lines()
+			.filter_map(|line| line.split_once(':'))
+			.find(|(key, _)| key.trim() == "microcode")
+			.and_then(|(_, value)| parse(value))?;
+
+		Some( Microcode::new(revision, Source::ProcCpuinfo) )
+	}
+}
+
+
+
+impl core::fmt::Display for Microcode {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		write!(f, "0x{:X} ({})", self.revision, self.source)
+	}
+}
+
+
+
+/// Source of a microcode revision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+	/// `/sys/devices/system/cpu/cpuN/microcode/version`.
+	Sysfs,
+
+	/// `microcode` field of `/proc/cpuinfo`.
+	ProcCpuinfo,
+
+	/// MSR 0x8B through `/dev/cpu/N/msr`.
+	MSR,
+}
+
+
+impl core::fmt::Display for Source {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		let args = match *self {
+			Source::Sysfs       => "sysfs",
+			Source::ProcCpuinfo => "/proc/cpuinfo",
+			Source::MSR         => "MSR 0x8B",
+		};
+
+		f.write_str(args)
+	}
+}
+
+
+
+/// Minimum microcode revision for a signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Requirement {
+	/// Vendor of the CPU.
+	vendor: CPUVendor,
+
+	/// Signature of the CPU. A missing stepping matches any stepping.
+	signature: Signature,
+
+	/// Minimum revision.
+	minimum: u32,
+}
+
+
+impl Requirement {
+	/// Creates a minimum revision requirement.
+	pub const fn new(vendor: CPUVendor, signature: Signature, minimum: u32) -> Self {
+		Requirement { vendor, signature, minimum }
+	}
+
+	/// Returns the vendor.
+	pub fn vendor(&self) -> CPUVendor {
+		self.vendor
+	}
+
+	/// Returns the signature.
+	pub fn signature(&self) -> Signature {
+		self.signature
+	}
+
+	/// Returns the minimum revision.
+	pub fn minimum(&self) -> u32 {
+		self.minimum
+	}
+
+	/// Returns `true` if the requirement applies to the given model.
+	pub fn matches(&self, model: &Model) -> bool {
+		let signature = model.signature();
+
+		let stepping = match self.signature.stepping() {
+			Some(s) => signature.stepping() == Some(s),
+			None => true,
+		};
+
+		(self.vendor == model.vendor()) && (self.signature.family() == signature.family()) && (self.signature.model() == signature.model()) && stepping
+	}
+}
+
+
+
+/// Microcode policy made of minimum revisions supplied by the caller.
+/// The first requirement matching the model applies.
+#[derive(Debug, Clone, Copy)]
+pub struct Policy<'a> {
+	requirements: &'a [Requirement],
+}
+
+
+impl<'a> Policy<'a> {
+	/// Creates a policy from a list of requirements.
+	pub const fn new(requirements: &'a [Requirement]) -> Self {
+		Policy { requirements }
+	}
+
+	/// Returns the requirements.
+	pub fn requirements(&self) -> &'a [Requirement] {
+		self.requirements
+	}
+
+	/// Checks the microcode revision of the given model against the policy.
+	pub fn check(&self, model: &Model, revision: Option<u32>) -> Compliance {
+		let minimum = match self.requirements.iter().find(|r| r.matches(model)) {
+			Some(r) => r.minimum,
+			_ => return Compliance::Unlisted,
+		};
+
+		match revision {
+			None => Compliance::Unknown { minimum },
+			Some(rev) if rev >= minimum => Compliance::UpToDate { minimum, revision: rev },
+			Some(rev) => Compliance::Outdated { minimum, revision: rev },
+		}
+	}
+}
+
+
+
+/// Result of a microcode policy check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compliance {
+	/// The revision is at least the minimum.
+	UpToDate { minimum: u32, revision: u32 },
+
+	/// The revision is older than the minimum.
+	Outdated { minimum: u32, revision: u32 },
+
+	/// The policy has a minimum but the revision is unknown.
+	Unknown { minimum: u32 },
+
+	/// The policy has no minimum for the model.
+	Unlisted,
+}
+
+
+impl Compliance {
+	/// Returns `true` if the revision is known to satisfy the policy or the policy does not apply.
+	pub fn ok(&self) -> bool {
+		matches!(self, Compliance::UpToDate { .. } | Compliance::Unlisted)
+	}
+}
+
+
+impl core::fmt::Display for Compliance {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		match *self {
+			Compliance::UpToDate { minimum, revision } => write!(f, "Microcode 0x{:X} is up to date (minimum 0x{:X})", revision, minimum),
+			Compliance::Outdated { minimum, revision } => write!(f, "Microcode 0x{:X} is outdated (minimum 0x{:X})", revision, minimum),
+			Compliance::Unknown { minimum }            => write!(f, "Microcode revision unknown (minimum 0x{:X})", minimum),
+			Compliance::Unlisted                       => f.write_str("No microcode requirement for this CPU"),
+		}
+	}
+}
+
+
+
+/// Parses a revision written in hexadecimal, with or without `0x`.
+#[cfg(feature = "std")]
+fn parse(s: &str) -> Option<u32> {
+	let s = s.trim();
+	let s = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")).unwrap_or(s);
+
+	u32::from_str_radix(s, 16).ok()
+}
+
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use crate::cpuid::{ CPUID, Registers };
+
+	use std::format;
+
+	/// Builds the model of an Intel dump with the given signature.
+	fn model(eax: u32) -> Model {
+		let mut cpuid = CPUID::empty();
+
+		cpuid.insert(0x00, 0, Registers { eax: 0x01, ebx: 0x756E6547, ecx: 0x6C65746E, edx: 0x49656E69 });
+		cpuid.insert(0x01, 0, Registers { eax, ..Registers::default() });
+
+		Model::from(&cpuid)
+	}
+
+	#[test]
+	#[cfg(feature = "std")]
+	fn parse() {
+		assert_eq!(super::parse("0xf0\n"), Some(0xF0));
+		assert_eq!(super::parse(" 0X8701021"), Some(0x08701021));
+		assert_eq!(super::parse("de"), Some(0xDE));
+
+		assert_eq!(super::parse("0x"), None);
+		assert_eq!(super::parse("microcode"), None);
+		assert_eq!(super::parse("0x100000000"), None);
+	}
+
+	#[test]
+	fn check() {
+		const REQUIREMENTS: [Requirement; 2] = [
+			Requirement::new(CPUVendor::Intel, Signature::new(0x06, 0x9E, Some(0x0A)), 0xF0),
+			Requirement::new(CPUVendor::Intel, Signature::new(0x06, 0x9E, None), 0xDE),
+		];
+
+		let policy = Policy::new(&REQUIREMENTS);
+
+		// The first matching requirement applies.
+		let coffeelake = model(0x000906EA);
+
+		assert_eq!(policy.check(&coffeelake, Some(0xF0)), Compliance::UpToDate { minimum: 0xF0, revision: 0xF0 });
+		assert_eq!(policy.check(&coffeelake, Some(0xEA)), Compliance::Outdated { minimum: 0xF0, revision: 0xEA });
+		assert_eq!(policy.check(&coffeelake, None), Compliance::Unknown { minimum: 0xF0 });
+
+		// A requirement without stepping matches any stepping.
+		let kabylake = model(0x000906E9);
+
+		assert_eq!(policy.check(&kabylake, Some(0xEA)), Compliance::UpToDate { minimum: 0xDE, revision: 0xEA });
+
+		// Other models and vendors are not listed.
+		assert_eq!(policy.check(&model(0x000806EA), Some(0x00)), Compliance::Unlisted);
+		assert_eq!(policy.check(&Model::empty(), None), Compliance::Unlisted);
+	}
+
+	#[test]
+	fn compliance() {
+		let uptodate = Compliance::UpToDate { minimum: 0xF0, revision: 0xF4 };
+		let outdated = Compliance::Outdated { minimum: 0xF0, revision: 0xEA };
+		let unknown  = Compliance::Unknown { minimum: 0xF0 };
+
+		assert!(uptodate.ok());
+		assert!(Compliance::Unlisted.ok());
+		assert!(!outdated.ok());
+		assert!(!unknown.ok());
+
+		assert_eq!(format!("{}", outdated), "Microcode 0xEA is outdated (minimum 0xF0)");
+		assert_eq!(format!("{}", unknown), "Microcode revision unknown (minimum 0xF0)");
+		assert_eq!(format!("{}", Microcode::new(0xF4, Source::Sysfs)), "0xF4 (sysfs)");
+	}
+}
@@ -9,29 +9,33 @@
 
 
 
+mod brand;
+mod errata;
+mod microcode;
 mod model;
 
 
-pub use self::model::Model;
+pub use self::brand::Brand;
 
+pub use self::errata::{ Erratum, Impact };
 
-#[cfg(target_arch = "x86")]
-use core::arch::x86::{
-	__cpuid       as cpuid,
-	__cpuid_count as cpuidex,
-};
+pub use self::microcode::{ Compliance, Microcode, Policy, Requirement, Source };
 
-#[cfg(target_arch = "x86_64")]
-use core::arch::x86_64::{
-	__cpuid       as cpuid,
-	__cpuid_count as cpuidex,
+pub use self::model::{
+	Caveat, CoreClass, CoreType, CPUModel, CPUVendor, Lineage, Model, ModelEntry, ModelInfo, Notation,
+	ParseSignatureError, Platform, ProcessorType, Profile, Segment, Signature, SignatureDisplay, Uarch,
 };
 
 
+use crate::cpuid::CPUID;
+use crate::error::Error;
+
+
 
 #[derive(Debug, Clone)]
 pub struct CPUInfo {
 	/// x86 or x64 architecture.
+	#[allow(dead_code)]
 	x64: bool,
 
 	/// Base and max CPU frequency.
@@ -40,11 +44,14 @@ pub struct CPUInfo {
 	/// Vendor & UArch.
 	model: Model,
 
-	/// Vendor brand encoded in UTF-8.
-	vbrand: String,
+	/// Vendor brand.
+	vbrand: Brand,
 
-	/// Processor brand encoded in UTF-8.
-	pbrand: String,
+	/// Processor brand.
+	pbrand: Brand,
+
+	/// Microcode revision, if known.
+	microcode: Option<Microcode>,
 
 	/// Vendor ID.
 	vid: u32,
@@ -61,13 +68,13 @@ impl CPUInfo {
 	}
 
 	/// Returns the Vendor Brand of the CPU.
-	pub fn vbrand(&self) -> String {
-		self.vbrand.clone()
+	pub fn vbrand(&self) -> Brand {
+		self.vbrand
 	}
 
 	/// Returns the Product Brand of the CPU.
-	pub fn pbrand(&self) -> String {
-		self.pbrand.clone()
+	pub fn pbrand(&self) -> Brand {
+		self.pbrand
 	}
 
 	/// Return the model of the CPU.
@@ -75,45 +82,78 @@ impl CPUInfo {
 		self.model
 	}
 
+	/// Returns the running microcode revision, if known.
+	pub fn microcode(&self) -> Option<Microcode> {
+		self.microcode
+	}
+
+	/// Checks the running microcode revision against the given policy.
+	pub fn check_microcode(&self, policy: &Policy) -> Compliance {
+		policy.check(&self.model, self.microcode.map(|m| m.revision()))
+	}
+
+	/// Returns the known errata affecting the CPU.
+	/// With an unknown microcode revision, errata fixed by a microcode update are listed.
+	pub fn errata(&self) -> impl Iterator<Item = &'static Erratum> {
+		Erratum::find(self.model, self.microcode.map(|m| m.revision()))
+	}
+
 	/// Returns the VID and PID of the CPU.
 	pub fn ids(&self) -> (u32, u32) {
 		(self.vid, self.pid)
 	}
 
-	/// Reads the CPU Info from the CPUID results.
+	/// Reads the CPU Info from the cached CPUID dump and the microcode revision from the OS.
 	pub fn read() -> CPUInfo {
-		// Get number of low and high leafs.
-		let lo = unsafe { cpuid(0x00000000).eax };
-		let hi = unsafe { cpuid(0x80000000).eax };
+		CPUInfo { microcode: Microcode::read(), ..Self::from(crate::cache::get().cpuid()) }
+	}
 
+	/// Reads the CPU Info of the host, or returns an error if the host does not
+	/// report its model or reports a brand that is not valid UTF-8.
+	pub fn try_read() -> Result<CPUInfo, Error> {
+		Ok( CPUInfo { microcode: Microcode::read(), ..Self::try_from(crate::cache::try_get()?.cpuid())? } )
+	}
+
+	/// Decodes the CPU Info from a CPUID dump, or returns an error if the dump
+	/// does not report the model or has a brand that is not valid UTF-8.
+	/// Missing brand and frequency leaves are not errors, as on older CPUs.
+	pub fn try_from(cpuid: &CPUID) -> Result<CPUInfo, Error> {
+		let model = Model::try_from(cpuid)?;
+
+		let info = Self::from(cpuid);
+
+		info.vbrand.to_str()?;
+		info.pbrand.to_str()?;
+
+		Ok( CPUInfo { model, ..info } )
+	}
+
+	/// Decodes the CPU Info from a CPUID dump. The microcode revision is not known.
+	pub fn from(cpuid: &CPUID) -> CPUInfo {
 		let mut x64 = false;
 
 		let (mut base, mut max) = (0, 0);
 
 		let (mut vid, mut pid) = (0, 0);
 
-		let (mut vbrand, mut pbrand) = (String::with_capacity(48), String::with_capacity(48));
+		let mut vbrand = Brand::empty();
 
 		// Get CPU frequencies.
-		if lo >= 0x00000016 {
-			let info = unsafe { cpuid(0x00000016) };
-
+		if let Some(info) = cpuid.leaf(0x00000016) {
 			base = info.eax;
 			max  = info.ebx;
 		}
 
 
 		// Get CPU VID and PID. Read if possible Vendor Brand.
-		if lo >= 0x00000017 {
-			let info = unsafe { cpuid(0x00000017) };
-
+		if let Some(info) = cpuid.leaf(0x00000017) {
 			vid = info.ebx;
 			pid = info.ecx;
 
 			let mut rawstr = [0u32; 12];
 
 			for i in 0..core::cmp::min(info.eax, 3) {
-				let raw = unsafe { cpuidex(0x00000017, i + 1) };
+				let raw = cpuid.subleaf(0x00000017, i + 1).unwrap_or_default();
 
 				rawstr[(i * 4    ) as usize] = raw.eax;
 				rawstr[(i * 4 + 1) as usize] = raw.ebx;
@@ -121,60 +161,25 @@ impl CPUInfo {
 				rawstr[(i * 4 + 3) as usize] = raw.edx;
 			}
 
-			let mut last = 0;
-
-			let rawstr = unsafe { core::slice::from_raw_parts( &rawstr as *const u32 as *const u8, 12 * 4) };
-
-			for c in rawstr {
-				if *c == 0 { break }
-				last += 1;
-			}
-
-			vbrand = unsafe { String::from_utf8_unchecked(rawstr[0..last].to_vec()) };
+			vbrand = Brand::from_registers(&rawstr);
 		}
 
 		// Check if x64.
-		if hi >= 0x80000001 {
-			let info = unsafe { cpuid(0x80000001) };
-
+		if let Some(info) = cpuid.leaf(0x80000001) {
 			x64 = ((info.edx >> 29) & 1) == 1;
 		}
 
-		// Get CPU Product Brand.
-		if hi >= 0x80000004 {
-			let mut rawstr = [0u32; 12];
-
-			for i in 0..3 {
-				let raw = unsafe { cpuid(0x80000002 + i) };
-
-				rawstr[(i * 4    ) as usize] = raw.eax;
-				rawstr[(i * 4 + 1) as usize] = raw.ebx;
-				rawstr[(i * 4 + 2) as usize] = raw.ecx;
-				rawstr[(i * 4 + 3) as usize] = raw.edx;
-			}
-
-			let mut last = 0;
-
-			let rawstr = unsafe { core::slice::from_raw_parts( &rawstr as *const u32 as *const u8, 12 * 4) };
-
-			for c in rawstr {
-				if *c == 0 { break }
-				last += 1;
-			}
-
-			pbrand = unsafe { String::from_utf8_unchecked(rawstr[0..last].to_vec()) };
-		}
-
-
 		CPUInfo {
 			x64,
 
-			model: Model::read(),
+			model: Model::from(cpuid),
 
 			freq: (base, max),
 
+			microcode: None,
+
 			vbrand,
-			pbrand,
+			pbrand: Brand::product(cpuid),
 
 			vid,
 			pid,
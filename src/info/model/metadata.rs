@@ -0,0 +1,303 @@
+//! Structured metadata of a CPU model.
+//! Generated from `data/models.txt` by the build script.
+
+
+// This Source Code Form is subject to the terms of the
+// Mozilla Public License, v. 2.0. If a copy of the MPL
+// was not distributed with this file, You can obtain one
+// at https://mozilla.org/MPL/2.0/.
+
+
+
+use super::Uarch;
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModelInfo {
+	/// Microarchitecture.
+	uarch: Uarch,
+
+	/// Microarchitecture of the second kind of cores of hybrid models.
+	secondary: Option<Uarch>,
+
+	/// Product codenames.
+	codenames: &'static [&'static str],
+
+	/// Process nodes in nm, in order of introduction.
+	node: &'static [u16],
+
+	/// Market segments.
+	segments: &'static [Segment],
+
+	/// Product families.
+	products: &'static [&'static str],
+
+	/// Launch year.
+	year: Option<u16>,
+
+	/// Type of the cores.
+	core: CoreType,
+}
+
+
+impl ModelInfo {
+	/// Creates the metadata of a model.
+	/// Only called by the generated model table, one argument per column.
+	#[allow(clippy::too_many_arguments)]
+	pub(super) const fn new(
+		uarch: Uarch,
+		secondary: Option<Uarch>,
+		codenames: &'static [&'static str],
+		node: &'static [u16],
+		segments: &'static [Segment],
+		products: &'static [&'static str],
+		year: Option<u16>,
+		core: CoreType,
+	) -> Self {
+		ModelInfo { uarch, secondary, codenames, node, segments, products, year, core }
+	}
+
+	/// Returns the microarchitecture.
+	pub fn uarch(&self) -> Uarch {
+		self.uarch
+	}
+
+	/// Returns the microarchitecture of the second kind of cores of hybrid
+	/// models: the E-cores of Intel and the compact cores of AMD.
+	pub fn secondary(&self) -> Option<Uarch> {
+		self.secondary
+	}
+
+	/// Returns the product codenames.
+	pub fn codenames(&self) -> &'static [&'static str] {
+		self.codenames
+	}
+
+	/// Returns the process nodes in nm, in order of introduction.
+	pub fn node(&self) -> &'static [u16] {
+		self.node
+	}
+
+	/// Returns the market segments.
+	pub fn segments(&self) -> &'static [Segment] {
+		self.segments
+	}
+
+	/// Returns `true` if the model is sold in the given market segment.
+	pub fn segment(&self, segment: Segment) -> bool {
+		self.segments.contains(&segment)
+	}
+
+	/// Returns the product families.
+	pub fn products(&self) -> &'static [&'static str] {
+		self.products
+	}
+
+	/// Returns the launch year, if known.
+	pub fn year(&self) -> Option<u16> {
+		self.year
+	}
+
+	/// Returns the type of the cores.
+	pub fn core(&self) -> CoreType {
+		self.core
+	}
+
+	/// Returns the platform of the model.
+	/// Server dies sold as high end desktop parts are `Server`,
+	/// client dies sold as entry level servers are `Client`.
+	pub fn platform(&self) -> Platform {
+		match self.segment(Segment::Server) && !self.segment(Segment::Mobile) {
+			true => Platform::Server,
+			_ => Platform::Client,
+		}
+	}
+}
+
+
+
+impl core::fmt::Display for ModelInfo {
+	/// Formats the metadata as `uarch [+ secondary] - codenames (node)`,
+	/// followed by the segments and product families on a second line.
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		f.write_str(self.uarch.name())?;
+
+		if let Some(secondary) = self.secondary {
+			write!(f, " + {}", secondary.name())?;
+		}
+
+		for (i, codename) in self.codenames.iter().enumerate() {
+			f.write_str(if i == 0 { " - " } else { " | " })?;
+			f.write_str(codename)?;
+		}
+
+		for (i, node) in self.node.iter().enumerate() {
+			f.write_str(if i == 0 { " (" } else { " - " })?;
+			write!(f, "{} nm", node)?;
+		}
+
+		if !self.node.is_empty() { f.write_str(")")?; }
+
+		if self.segments.is_empty() && self.products.is_empty() { return Ok(()) }
+
+		f.write_str("\n")?;
+
+		for (i, segment) in self.segments.iter().enumerate() {
+			f.write_str(if i == 0 { "[" } else { ", " })?;
+			write!(f, "{}", segment)?;
+		}
+
+		if !self.segments.is_empty() { f.write_str("] ")?; }
+
+		for (i, product) in self.products.iter().enumerate() {
+			if i != 0 { f.write_str(" | ")?; }
+			f.write_str(product)?;
+		}
+
+		Ok(())
+	}
+}
+
+
+
+/// Market segment of a CPU model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Segment {
+	/// Desktop and high end desktop.
+	Desktop,
+
+	/// Laptops and handhelds.
+	Mobile,
+
+	/// Servers and workstations.
+	Server,
+
+	/// Embedded and industrial.
+	Embedded,
+}
+
+
+impl core::fmt::Display for Segment {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		let args = match *self {
+			Segment::Desktop  => "Desktop",
+			Segment::Mobile   => "Mobile",
+			Segment::Server   => "Server",
+			Segment::Embedded => "Embedded",
+		};
+
+		f.write_str(args)
+	}
+}
+
+
+
+/// Type of the cores of a CPU model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CoreType {
+	/// Performance (big) cores only.
+	Performance,
+
+	/// Efficiency (small) cores only.
+	Efficiency,
+
+	/// Area optimized variant of a performance core (e.g. Zen 4c).
+	Dense,
+
+	/// Mix of performance and efficiency cores.
+	Hybrid,
+}
+
+
+impl core::fmt::Display for CoreType {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		let args = match *self {
+			CoreType::Performance => "Performance",
+			CoreType::Efficiency  => "Efficiency",
+			CoreType::Dense       => "Dense",
+			CoreType::Hybrid      => "Hybrid",
+		};
+
+		f.write_str(args)
+	}
+}
+
+
+
+/// Class of a microarchitecture, common to all vendors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CoreClass {
+	/// Wide, high performance core (e.g. Golden Cove, Zen).
+	Big,
+
+	/// Narrow, area and power optimized core (e.g. Gracemont, Jaguar).
+	Small,
+}
+
+
+
+/// Platform lineage of a CPU model, common to all vendors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Platform {
+	/// Desktop, mobile and embedded dies.
+	Client,
+
+	/// Server dies, including their workstation and high end desktop parts.
+	Server,
+}
+
+
+
+/// Line of successive microarchitectures of a vendor.
+/// Generations are only comparable inside the same lineage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lineage {
+	/// Intel i486, Pentium, P6 and Core.
+	IntelCore,
+
+	/// Intel Pentium 4.
+	NetBurst,
+
+	/// Intel Quark.
+	Quark,
+
+	/// Intel Atom and E-cores.
+	IntelAtom,
+
+	/// Intel Xeon Phi.
+	IntelPhi,
+
+	/// AMD K5 to Zen, and the Hygon generations derived from Zen.
+	AMD,
+
+	/// AMD Bobcat family of low power cores.
+	AMDCat,
+
+	/// Centaur, VIA and Zhaoxin.
+	Centaur,
+
+	/// Centaur CNS.
+	CNS,
+
+	/// Cyrix 5x86 and 6x86.
+	Cyrix,
+
+	/// Cyrix MediaGX and Geode.
+	Geode,
+
+	/// NexGen.
+	NexGen,
+
+	/// Transmeta.
+	Transmeta,
+
+	/// Rise and SiS.
+	Rise,
+
+	/// UMC.
+	UMC,
+
+	/// DM&P Vortex86.
+	Vortex86,
+}
@@ -8,11 +8,26 @@
 
 
 
+mod metadata;
+mod profile;
+mod signature;
 mod uarch;
 mod vendor;
 
 
-pub use self::{ uarch::CPUModel, vendor::CPUVendor };
+use crate::cpuid::CPUID;
+use crate::info::Brand;
+use crate::error::Error;
+use crate::simd::SIMDFlags;
+
+
+pub use self::{
+	metadata::{ CoreClass, CoreType, Lineage, ModelInfo, Platform, Segment },
+	profile::{ Caveat, Profile },
+	signature::{ Notation, ParseSignatureError, ProcessorType, Signature, SignatureDisplay },
+	uarch::{ CPUModel, ModelEntry, Uarch },
+	vendor::CPUVendor,
+};
 
 #[derive(Debug, Clone, Copy)]
 pub struct Model {
@@ -21,6 +36,15 @@ pub struct Model {
 
 	/// Specific model of the CPU (family and model).
 	model: CPUModel,
+
+	/// Family, model and stepping.
+	signature: Signature,
+
+	/// Processor type.
+	ptype: ProcessorType,
+
+	/// Raw CPUID leaf 1 EAX.
+	eax: u32,
 }
 
 
@@ -30,19 +54,41 @@ impl Model {
 		Model {
 			vendor: CPUVendor::Unknown,
 			model: CPUModel::Unknown(0x00),
+			signature: Signature::empty(),
+			ptype: ProcessorType::OEM,
+			eax: 0,
 		}
 	}
 
-	/// Reads the model information from the CPUID registers.
+	/// Returns the model information of the host, read once per process.
 	pub fn read() -> Self {
-		match unsafe { core::arch::x86_64::__cpuid(0).eax } {
-			0 => Model::empty(),
-			_ => {
-				let mut model = Model::empty();
-				model.decode();
-				model
-			},
-		}
+		crate::cache::get().model()
+	}
+
+	/// Decodes the model information from a CPUID dump.
+	pub fn from(cpuid: &CPUID) -> Self {
+		let mut model = Model::empty();
+		model.decode(cpuid);
+		model
+	}
+
+	/// Returns the model information of the host, or an error if it does not
+	/// report it. A model the crate does not know is `CPUModel::Unknown`.
+	pub fn try_read() -> Result<Self, Error> {
+		Self::try_from(crate::cache::try_get()?.cpuid())
+	}
+
+	/// Decodes the model information from a CPUID dump, or returns an error if
+	/// the dump does not report it.
+	pub fn try_from(cpuid: &CPUID) -> Result<Self, Error> {
+		let leaf0 = cpuid.try_leaf(0x00)?;
+
+		if leaf0.eax < 0x01 { return Err(Error::LeafUnsupported(0x01)); }
+
+		// A family of 0 is not a valid signature.
+		if cpuid.try_leaf(0x01)?.eax == 0 { return Err(Error::InconsistentData); }
+
+		Ok(Self::from(cpuid))
 	}
 
 	/// Returns the vendor.
@@ -55,31 +101,56 @@ impl Model {
 		self.model
 	}
 
+	/// Returns the decoded family number.
+	pub fn family(&self) -> u16 {
+		self.signature.family()
+	}
+
+	/// Returns the decoded model number.
+	pub fn model_number(&self) -> u8 {
+		self.signature.model()
+	}
+
+	/// Returns the stepping.
+	pub fn stepping(&self) -> u8 {
+		self.signature.stepping().unwrap_or(0)
+	}
+
+	/// Returns the processor type.
+	pub fn ptype(&self) -> ProcessorType {
+		self.ptype
+	}
+
+	/// Returns the family, model and stepping signature.
+	pub fn signature(&self) -> Signature {
+		self.signature
+	}
+
+	/// Returns the raw CPU signature (CPUID leaf 1 EAX).
+	pub fn raw_signature(&self) -> u32 {
+		self.eax
+	}
+
 	/// Decodes the model information from the raw CPUID registers.
-	fn decode(&mut self) {
+	fn decode(&mut self, cpuid: &CPUID) {
 		// First decode the vendor.
-		let leaf0 = unsafe { core::arch::x86_64::__cpuid(0x00) };
+		let leaf0 = match cpuid.leaf(0x00) {
+			Some(leaf0) if leaf0.eax != 0 => leaf0,
+			_ => return,
+		};
 
 		self.vendor = CPUVendor::from( (leaf0.ebx, leaf0.edx, leaf0.ecx) );
 
 
 		// Then decode the model and family.
-		if leaf0 .eax >= 0x01 {
-			let eax = unsafe { core::arch::x86_64::__cpuid(0x01).eax };
-
-			let _stepping = (eax      ) & 0x0F;
-			let bmodel   = (eax >>  4) & 0x0F;
-			let bfamily  = (eax >>  8) & 0x0F;
+		if let Some(leaf1) = cpuid.leaf(0x01) {
+			let eax = leaf1.eax;
 
-			let _ptype    = (eax >> 12) & 0x03;
+			self.eax       = eax;
+			self.ptype     = ProcessorType::from_eax(eax);
+			self.signature = Signature::decode(self.vendor, eax);
 
-			let xmodel   = (eax >> 16) & 0x0F;
-			let xfamily  = (eax >> 20) & 0xFF;
-
-			let family = bfamily + (xfamily    );
-			let model  = bmodel  + (xmodel << 4);
-
-			self.model = CPUModel::from(self.vendor, family, model, xmodel);
+			self.model = CPUModel::from(self.vendor, self.signature).refine(&SIMDFlags::from(cpuid), &Brand::product(cpuid));
 		}
 	}
 }
@@ -89,6 +160,9 @@ impl Model {
 
 impl core::fmt::Display for Model {
 	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
-		write!(f, "{}\n{}\n", self.vendor, self.model)
+		match self.eax {
+			0 => write!(f, "{}\nModel not reported by CPUID\n", self.vendor),
+			_ => write!(f, "{}\n{}\n", self.vendor, self.model),
+		}
 	}
 }
\ No newline at end of file
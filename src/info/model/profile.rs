@@ -0,0 +1,92 @@
+//! Static execution resources of a microarchitecture.
+//! Generated from `data/uarch.txt` and `data/models.txt` by the build script.
+
+
+// This Source Code Form is subject to the terms of the
+// Mozilla Public License, v. 2.0. If a copy of the MPL
+// was not distributed with this file, You can obtain one
+// at https://mozilla.org/MPL/2.0/.
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Profile {
+	/// Native vector datapath width in bits. 0 if there are no vector units.
+	width: u16,
+
+	/// Number of FMA units of the native width.
+	fma: u8,
+
+	/// Instructions or widths known to be slow.
+	caveats: &'static [Caveat],
+}
+
+
+impl Profile {
+	/// Creates an execution profile.
+	pub(super) const fn new(width: u16, fma: u8, caveats: &'static [Caveat]) -> Self {
+		Profile { width, fma, caveats }
+	}
+
+	/// Returns the native vector datapath width in bits.
+	/// Wider operations are split into several micro operations.
+	pub fn width(&self) -> u16 {
+		self.width
+	}
+
+	/// Returns the number of FMA units of the native width.
+	pub fn fma(&self) -> u8 {
+		self.fma
+	}
+
+	/// Returns the known caveats.
+	pub fn caveats(&self) -> &'static [Caveat] {
+		self.caveats
+	}
+
+	/// Returns `true` if the given caveat applies.
+	pub fn caveat(&self, caveat: Caveat) -> bool {
+		self.caveats.contains(&caveat)
+	}
+}
+
+
+
+/// Known performance caveat of a microarchitecture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Caveat {
+	/// 128-bit operations are split into two 64-bit halves.
+	SplitFP128,
+
+	/// 256-bit operations are split into two 128-bit halves.
+	/// Reported by AMD in CPUID leaf 0x8000001A (FP256 clear).
+	SplitFP256,
+
+	/// 512-bit operations are split into two 256-bit halves.
+	SplitFP512,
+
+	/// Some SKUs only have one 512-bit FMA unit, `Profile::fma` gives the maximum.
+	SingleFMA512,
+
+	/// Heavy 512-bit code lowers the core frequency.
+	AVX512Throttle,
+
+	/// PDEP and PEXT are microcoded, with a latency depending on the operands.
+	SlowPdepPext,
+}
+
+
+impl core::fmt::Display for Caveat {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		let args = match *self {
+			Caveat::SplitFP128     => "128-bit operations split in two 64-bit halves",
+			Caveat::SplitFP256     => "256-bit operations split in two 128-bit halves",
+			Caveat::SplitFP512     => "512-bit operations split in two 256-bit halves",
+			Caveat::SingleFMA512   => "Single 512-bit FMA unit on some SKUs",
+			Caveat::AVX512Throttle => "Frequency drop on heavy 512-bit code",
+			Caveat::SlowPdepPext   => "Microcoded PDEP and PEXT",
+		};
+
+		f.write_str(args)
+	}
+}
@@ -0,0 +1,365 @@
+//! CPU signature (family, model and stepping).
+//! Decoded from CPUID register 1.
+
+
+// This Source Code Form is subject to the terms of the
+// Mozilla Public License, v. 2.0. If a copy of the MPL
+// was not distributed with this file, You can obtain one
+// at https://mozilla.org/MPL/2.0/.
+
+
+
+use core::str::FromStr;
+
+use super::CPUVendor;
+
+
+
+/// Highest family: base family 0xF plus an 8-bit extended family.
+const MAXFAMILY: u32 = 0x10E;
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Signature {
+	/// Decoded family (base + extended).
+	family: u16,
+
+	/// Decoded model (base + extended).
+	model: u8,
+
+	/// Stepping. Not present in the `FF_MMH` notation.
+	stepping: Option<u8>,
+}
+
+
+impl Signature {
+	/// Creates an empty signature.
+	pub const fn empty() -> Self {
+		Signature { family: 0, model: 0, stepping: None }
+	}
+
+	/// Creates a signature from its decoded fields.
+	pub const fn new(family: u16, model: u8, stepping: Option<u8>) -> Self {
+		Signature { family, model, stepping }
+	}
+
+	/// Decodes the signature from the EAX register of CPUID leaf 1.
+	/// Used when the vendor is not known, it follows the generic rule:
+	/// the extended family is added to a base family of 0xF and the
+	/// extended model is used for base families 6 and above.
+	pub fn from_eax(eax: u32) -> Self {
+		Self::decode(CPUVendor::Unknown, eax)
+	}
+
+	/// Decodes the signature from the EAX register of CPUID leaf 1
+	/// following the rules of the given vendor.
+	pub fn decode(vendor: CPUVendor, eax: u32) -> Self {
+		let stepping = (eax      ) & 0x0F;
+		let bmodel   = (eax >>  4) & 0x0F;
+		let bfamily  = (eax >>  8) & 0x0F;
+
+		let xmodel   = (eax >> 16) & 0x0F;
+		let xfamily  = (eax >> 20) & 0xFF;
+
+		// The extended family is only valid for a base family of 0xF.
+		let family = match bfamily {
+			0x0F => bfamily + xfamily,
+			_ => bfamily,
+		};
+
+		// The extended model is valid for a vendor specific set of base families.
+		let extended = match vendor {
+			CPUVendor::Intel => (bfamily == 0x06) || (bfamily == 0x0F),
+			CPUVendor::AMD | CPUVendor::Hygon => bfamily == 0x0F,
+			_ => bfamily >= 0x06,
+		};
+
+		let model = match extended {
+			true => bmodel + (xmodel << 4),
+			_ => bmodel,
+		};
+
+		Signature { family: family as u16, model: model as u8, stepping: Some(stepping as u8) }
+	}
+
+	/// Returns the family.
+	pub fn family(&self) -> u16 {
+		self.family
+	}
+
+	/// Returns the model.
+	pub fn model(&self) -> u8 {
+		self.model
+	}
+
+	/// Returns the stepping, if known.
+	pub fn stepping(&self) -> Option<u8> {
+		self.stepping
+	}
+
+	/// Encodes the signature back into the layout of CPUID leaf 1 EAX.
+	/// The processor type bits are left empty and a missing stepping is encoded as 0.
+	pub fn raw(&self) -> u32 {
+		let family = self.family as u32;
+		let model  = self.model  as u32;
+
+		let (bfamily, xfamily) = match family {
+			f if f >= 0x0F => (0x0F, f - 0x0F),
+			f => (f, 0),
+		};
+
+		let stepping = self.stepping.unwrap_or(0) as u32;
+
+		(xfamily << 20) | ((model >> 4) << 16) | (bfamily << 8) | ((model & 0x0F) << 4) | (stepping & 0x0F)
+	}
+
+	/// Returns a formatter for the given notation.
+	pub fn notation(&self, notation: Notation) -> SignatureDisplay {
+		SignatureDisplay { signature: *self, notation }
+	}
+}
+
+
+
+impl core::fmt::Display for Signature {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		match self.stepping {
+			Some(_) => self.notation(Notation::Verbose).fmt(f),
+			None    => self.notation(Notation::FamilyModel).fmt(f),
+		}
+	}
+}
+
+
+
+impl FromStr for Signature {
+	type Err = ParseSignatureError;
+
+	/// Parses any of the notations in `Notation`:
+	/// `06_9EH`, `0x906EA` or `family 6 model 158 stepping 10`.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let s = s.trim();
+
+		// Raw CPUID signature.
+		if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+			let eax = u32::from_str_radix(hex, 16).map_err(|_| ParseSignatureError)?;
+			return Ok( Signature::from_eax(eax) );
+		}
+
+		// Family and model (Intel SDM notation).
+		if let Some((family, model)) = s.split_once('_') {
+			let model = model.strip_suffix('H').or_else(|| model.strip_suffix('h')).unwrap_or(model);
+
+			let family = u16::from_str_radix(family, 16).map_err(|_| ParseSignatureError)?;
+			let model  = u8::from_str_radix(model, 16).map_err(|_| ParseSignatureError)?;
+
+			if (family as u32) > MAXFAMILY { return Err(ParseSignatureError); }
+
+			return Ok( Signature::new(family, model, None) );
+		}
+
+		// Verbose notation.
+		let mut words = s.split_whitespace();
+
+		let mut family = None;
+		let mut model = None;
+		let mut stepping = None;
+
+		while let Some(key) = words.next() {
+			let value = words.next().ok_or(ParseSignatureError)?;
+
+			let value = match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+				Some(hex) => u32::from_str_radix(hex, 16),
+				None => value.parse::<u32>(),
+			}.map_err(|_| ParseSignatureError)?;
+
+			let slot = match key {
+				k if k.eq_ignore_ascii_case("family")   => &mut family,
+				k if k.eq_ignore_ascii_case("model")    => &mut model,
+				k if k.eq_ignore_ascii_case("stepping") => &mut stepping,
+				_ => return Err(ParseSignatureError),
+			};
+
+			if slot.replace(value).is_some() { return Err(ParseSignatureError) }
+		}
+
+		match (family, model) {
+			(Some(family), Some(model)) if family <= MAXFAMILY && model <= 0xFF => match stepping {
+				Some(s) if s > 0x0F => Err(ParseSignatureError),
+				_ => Ok( Signature::new(family as u16, model as u8, stepping.map(|s| s as u8)) ),
+			},
+
+			_ => Err(ParseSignatureError),
+		}
+	}
+}
+
+
+
+/// Notations of a CPU signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Notation {
+	/// Intel SDM family and model notation (`06_9EH`).
+	FamilyModel,
+
+	/// Raw CPUID leaf 1 EAX value (`0x906EA`).
+	Hex,
+
+	/// Decimal notation used by `/proc/cpuinfo` (`family 6 model 158 stepping 10`).
+	Verbose,
+}
+
+
+
+/// Formats a `Signature` in a given `Notation`.
+#[derive(Debug, Clone, Copy)]
+pub struct SignatureDisplay {
+	signature: Signature,
+	notation: Notation,
+}
+
+
+impl core::fmt::Display for SignatureDisplay {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		let sig = &self.signature;
+
+		match self.notation {
+			Notation::FamilyModel => write!(f, "{:02X}_{:02X}H", sig.family, sig.model),
+
+			Notation::Hex => write!(f, "0x{:X}", sig.raw()),
+
+			Notation::Verbose => match sig.stepping {
+				Some(s) => write!(f, "family {} model {} stepping {}", sig.family, sig.model, s),
+				None    => write!(f, "family {} model {}", sig.family, sig.model),
+			},
+		}
+	}
+}
+
+
+
+/// Processor type. Bits 12 and 13 of CPUID leaf 1 EAX.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessorType {
+	/// Original OEM processor.
+	OEM,
+
+	/// Intel OverDrive processor.
+	OverDrive,
+
+	/// Dual processor.
+	Dual,
+
+	/// Reserved value.
+	Reserved,
+}
+
+
+impl ProcessorType {
+	/// Decodes the processor type from the EAX register of CPUID leaf 1.
+	pub fn from_eax(eax: u32) -> Self {
+		match (eax >> 12) & 0x03 {
+			0 => ProcessorType::OEM,
+			1 => ProcessorType::OverDrive,
+			2 => ProcessorType::Dual,
+			_ => ProcessorType::Reserved,
+		}
+	}
+}
+
+
+
+/// Error returned when parsing a `Signature` fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseSignatureError;
+
+
+impl core::fmt::Display for ParseSignatureError {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		f.write_str("invalid CPU signature")
+	}
+}
+
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::format;
+
+	#[test]
+	fn decode() {
+		let intel = Signature::decode(CPUVendor::Intel, 0x000906EA);
+		assert_eq!(intel, Signature::new(0x06, 0x9E, Some(0x0A)));
+
+		// AMD only uses the extended model for base family 0xF.
+		let zen3 = Signature::decode(CPUVendor::AMD, 0x00A20F10);
+		assert_eq!(zen3, Signature::new(0x19, 0x21, Some(0x00)));
+
+		let k7 = Signature::decode(CPUVendor::AMD, 0x00010681);
+		assert_eq!(k7, Signature::new(0x06, 0x08, Some(0x01)));
+
+		// The extended family is ignored below base family 0xF.
+		let p6 = Signature::decode(CPUVendor::Intel, 0x00F00695);
+		assert_eq!(p6, Signature::new(0x06, 0x09, Some(0x05)));
+	}
+
+	#[test]
+	fn raw() {
+		for eax in [0x000906EA, 0x00A20F10, 0x00000F41, 0x00000543, 0x0FF00F00] {
+			assert_eq!(Signature::from_eax(eax).raw(), eax);
+		}
+	}
+
+	#[test]
+	fn parse_hex() {
+		assert_eq!("0x906EA".parse(), Ok(Signature::new(0x06, 0x9E, Some(0x0A))));
+		assert_eq!("0X906ea".parse(), Ok(Signature::new(0x06, 0x9E, Some(0x0A))));
+
+		assert_eq!("0x".parse::<Signature>(), Err(ParseSignatureError));
+		assert_eq!("0x1FFFFFFFF".parse::<Signature>(), Err(ParseSignatureError));
+	}
+
+	#[test]
+	fn parse_family_model() {
+		assert_eq!("06_9EH".parse(), Ok(Signature::new(0x06, 0x9E, None)));
+		assert_eq!("19_21h".parse(), Ok(Signature::new(0x19, 0x21, None)));
+		assert_eq!("10E_00".parse(), Ok(Signature::new(0x10E, 0x00, None)));
+
+		assert_eq!("10F_00H".parse::<Signature>(), Err(ParseSignatureError));
+		assert_eq!("FFF_00H".parse::<Signature>(), Err(ParseSignatureError));
+		assert_eq!("06_100H".parse::<Signature>(), Err(ParseSignatureError));
+		assert_eq!("06_H".parse::<Signature>(), Err(ParseSignatureError));
+	}
+
+	#[test]
+	fn parse_verbose() {
+		assert_eq!("family 6 model 158 stepping 10".parse(), Ok(Signature::new(0x06, 0x9E, Some(0x0A))));
+		assert_eq!("Family 0x19 Model 0x21".parse(), Ok(Signature::new(0x19, 0x21, None)));
+
+		assert_eq!("family 271 model 0".parse::<Signature>(), Err(ParseSignatureError));
+		assert_eq!("family 6 model 256".parse::<Signature>(), Err(ParseSignatureError));
+		assert_eq!("family 6 model 158 stepping 16".parse::<Signature>(), Err(ParseSignatureError));
+		assert_eq!("family 6 family 6 model 158".parse::<Signature>(), Err(ParseSignatureError));
+		assert_eq!("family 6 core 1".parse::<Signature>(), Err(ParseSignatureError));
+		assert_eq!("family 6 model".parse::<Signature>(), Err(ParseSignatureError));
+	}
+
+	#[test]
+	fn display() {
+		let sig = Signature::new(0x06, 0x9E, Some(0x0A));
+
+		assert_eq!(format!("{}", sig.notation(Notation::FamilyModel)), "06_9EH");
+		assert_eq!(format!("{}", sig.notation(Notation::Hex)), "0x906EA");
+		assert_eq!(format!("{}", sig), "family 6 model 158 stepping 10");
+		assert_eq!(format!("{}", Signature::new(0x06, 0x9E, None)), "06_9EH");
+
+		for notation in [Notation::FamilyModel, Notation::Hex, Notation::Verbose] {
+			let parsed: Signature = format!("{}", sig.notation(notation)).parse().unwrap();
+
+			assert_eq!((parsed.family(), parsed.model()), (0x06, 0x9E));
+		}
+	}
+}
\ No newline at end of file
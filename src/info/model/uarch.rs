@@ -1,5 +1,6 @@
 //! Micro architecture of the processor.
 //! Contains the information of the specific model of the CPU.
+//! The model table is generated from `data/models.txt` by the build script.
 
 
 // This Source Code Form is subject to the terms of the
@@ -9,622 +10,431 @@
 
 
 
-use super::CPUVendor;
+use crate::simd::SIMDFlags;
 
+use crate::info::Brand;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum CPUModel {
-	// Intel
-	Pentium5(u8),
-	Pentium6(u8),
-	Quark(u8),
-	Dothan(u8),
-	Yonah(u8),
-	Conroe(u8),
-	Penryn(u8),
-	Nehalem(u8),
-	SandyBridge(u8),
-	IvyBridge(u8),
-	Haswell(u8),
-	Broadwell(u8),
-	SkyLake(u8),
-	PalmCove(u8),
-	SunnyCove(u8),
-	Bonnel(u8),
-	Saltwell(u8),
-	Silvermont(u8),
-	Airmont(u8),
-	Goldmont(u8),
-	GoldmontPlus(u8),
-	KnightsLanding(u8),
-	KnightsMill(u8),
-	Willamette(u8),
-	Prescott(u8),
-
-	// AMD 
-	K5(u8),
-	K6(u8),
-	Geode(u8),
-	K7(u8),
-	K8(u8),
-	K10(u8),
-	Bobcat(u8),
-	Bulldozer(u8),
-	Piledriver(u8),
-	Steamroller(u8),
-	Excavator(u8),
-	Puma(u8),
-	Jaguar(u8),
-	Zen(u8),
-	Zen2(u8),
-	Zen3(u8),
-
-	// Hygon
-	Dhyana(u8),
-
-	Unknown(u8),
-}
-
-
-impl CPUModel {
-	pub fn from(vendor: CPUVendor, family: u32, model: u32, xmodel: u32) -> CPUModel {
-		use CPUModel::*;
-
-		match vendor {
-			CPUVendor::Intel => match family {
-				0x05 => match model {
-					0x01 =>  Pentium5(0x01),
-					0x02 =>  Pentium5(0x02),
-					0x03 =>  Pentium5(0x03),
-					0x04 =>  Pentium5(0x04),
-
-					0x09 =>  Quark(0x09),
-
-					_ => Unknown(0x00),
-				},
-
-				0x06 => match model {
-					0x01 =>  Pentium6(0x01),
-					0x03 =>  Pentium6(0x03),
-					0x05 =>  Pentium6(0x05),
-					0x06 =>  Pentium6(0x06),
-					0x07 =>  Pentium6(0x07),
-					0x08 =>  Pentium6(0x08),
-					0x0A =>  Pentium6(0x0A),
-					0x0B =>  Pentium6(0x0B),
-
-					0x09 =>  Dothan(0x09),
-					0x0D =>  Dothan(0x0D),
-					0x15 =>  Dothan(0x15),
-
-					0x0E =>  Yonah(0x0E),
-
-					0x0F =>  Conroe(0x0F),
-					0x16 =>  Conroe(0x16),
-
-					0x17 =>  Penryn(0x17),
-					0x1D =>  Penryn(0x1D),
-
-					0x1A =>  Nehalem(0x1A),
-					0x1E =>  Nehalem(0x1E),
-					0x1F =>  Nehalem(0x1F),
-					0x2E =>  Nehalem(0x2E),
-					0x25 =>  Nehalem(0x25),
-					0x2C =>  Nehalem(0x2C),
-					0x2F =>  Nehalem(0x2F),
-
-					0x2A =>  SandyBridge(0x2A),
-					0x2D =>  SandyBridge(0x2D),
-
-					0x3A =>  IvyBridge(0x3A),
-					0x3E =>  IvyBridge(0x3E),
-
-					0x3C =>  Haswell(0x3C),
-					0x3F =>  Haswell(0x3F),
-					0x45 =>  Haswell(0x45),
-					0x46 =>  Haswell(0x46),
-
-					0x3D =>  Broadwell(0x3D),
-					0x47 =>  Broadwell(0x47),
-					0x4F =>  Broadwell(0x4F),
-					0x56 =>  Broadwell(0x56),
-
-					0x4E =>  SkyLake(0x4E),
-					0x55 =>  SkyLake(0x55),
-					0x5E =>  SkyLake(0x5E),
-					0x8E =>  SkyLake(0x8E),
-					0x9E =>  SkyLake(0x9E),
-					0xA5 =>  SkyLake(0xA5),
-					0xA6 =>  SkyLake(0xA6),
-
-					0x66 =>  PalmCove(0x66),
-
-					0x6A =>  SunnyCove(0x6A),
-					0x6C =>  SunnyCove(0x6C),
-					0x7D =>  SunnyCove(0x7D),
-					0x7E =>  SunnyCove(0x7E),
-
-					0x1C =>  Bonnel(0x1C),
-					0x26 =>  Bonnel(0x26),
-
-					0x27 =>  Saltwell(0x27),
-					0x35 =>  Saltwell(0x35),
-					0x36 =>  Saltwell(0x36),
-
-					0x37 =>  Silvermont(0x37),
-					0x4A =>  Silvermont(0x4A),
-					0x4D =>  Silvermont(0x4D),
-					0x5A =>  Silvermont(0x5A),
-					0x5D =>  Silvermont(0x5D),
-
-					0x4C =>  Airmont(0x4C),
-					0x75 =>  Airmont(0x75),
+use super::{ Caveat, CoreClass, CoreType, CPUVendor, Lineage, ModelInfo, Platform, Profile, Segment, Signature };
 
-					0x5C =>  Goldmont(0x5C),
-					0x5F =>  Goldmont(0x5F),
 
-					0x7A =>  GoldmontPlus(0x7A),
+include!(concat!(env!("OUT_DIR"), "/models.rs"));
 
-					0x57 =>  KnightsLanding(0x57),
-					0x85 =>  KnightsMill(0x85),
 
-					_ => Unknown(0x00),
-				},
 
-				0x0F => match model {
-					0x00 =>  Willamette(0x00),
-					0x01 =>  Willamette(0x01),
-					0x02 =>  Willamette(0x02),
+/// Entry of the model database.
+#[derive(Debug, Clone, Copy)]
+pub struct ModelEntry {
+	/// Vendor of the CPU.
+	vendor: CPUVendor,
 
-					0x03 =>  Prescott(0x03),
-					0x04 =>  Prescott(0x04),
-					0x06 =>  Prescott(0x06),
+	/// Decoded family.
+	family: u16,
 
-					_ => Unknown(0x00),
-				},
+	/// Inclusive range of decoded models.
+	model: (u8, u8),
 
-				_ => Unknown(0x00),
-			},
+	/// Inclusive range of steppings. `None` matches any stepping.
+	stepping: Option<(u8, u8)>,
 
-			CPUVendor::AMD => match family {
-				0x05 => match model {
-					0x00 =>  K5(0x00),
-					0x01 =>  K5(0x01),
-					0x02 =>  K5(0x02),
+	/// Variant of the model.
+	build: fn(u8) -> CPUModel,
 
-					0x06 =>  K6(0x06),
-					0x07 =>  K6(0x07),
-					0x08 =>  K6(0x08),
-					0x0D =>  K6(0x0D),
+	/// Metadata of the model.
+	info: ModelInfo,
 
-					0x0A =>  Geode(0x0A),
+	/// Execution profile of the model.
+	profile: Profile,
 
-					_ => Unknown(0x00),
-				},
-
-				0x06 =>  K7(0x06),
-
-				0x0F =>  K8(0x0F),
-
-				0x11 =>  K8(0x11),
-
-				0x10 =>  K10(0x10),
-				0x12 =>  K10(0x12),
-
-				0x14 =>  Bobcat(0x14),
-
-				0x15 => match model {
-					0x00 => Bulldozer(0x00),
-					0x01 => Bulldozer(0x01),
-
-					0x02 => Piledriver(0x02),
-					0x10 => Piledriver(0x10),
-					0x13 => Piledriver(0x13),
-
-					0x38 => Steamroller(0x38),
-					0x30 => Steamroller(0x30),
+	/// LLVM CPU name of the model.
+	llvm: Option<&'static str>,
+}
 
-					0x60 => Excavator(0x60),
-					0x65 => Excavator(0x65),
-					0x70 => Excavator(0x70),
 
-					_ => match xmodel {
-						0x00 =>  Bulldozer(0x00),
+impl ModelEntry {
+	/// Returns the vendor.
+	pub fn vendor(&self) -> CPUVendor {
+		self.vendor
+	}
 
-						0x01 =>  Piledriver(0x01),
-						0x02 =>  Piledriver(0x02),
+	/// Returns the decoded family.
+	pub fn family(&self) -> u16 {
+		self.family
+	}
 
-						0x03 =>  Steamroller(0x03),
-						0x04 =>  Steamroller(0x04),
+	/// Returns the inclusive range of decoded models.
+	pub fn models(&self) -> (u8, u8) {
+		self.model
+	}
 
-						_ =>  Unknown(0x00),
-					},
-				},
+	/// Returns the inclusive range of steppings, `None` if any stepping matches.
+	pub fn steppings(&self) -> Option<(u8, u8)> {
+		self.stepping
+	}
 
-				0x16 => match model {
-					n if n >= 3 =>  Puma(n as u8),
-					n =>  Jaguar(n as u8),
-				},
+	/// Returns the model of the first model number of the entry.
+	pub fn model(&self) -> CPUModel {
+		(self.build)(self.model.0)
+	}
 
-				0x17 => match model {
-					0x01 =>  Zen(0x01),
-					0x08 =>  Zen(0x08),
-					0x11 =>  Zen(0x11),
-					0x18 =>  Zen(0x18),
+	/// Returns the metadata of the model.
+	pub fn info(&self) -> ModelInfo {
+		self.info
+	}
 
-					0x31 =>  Zen2(0x31),
-					0x60 =>  Zen2(0x60),
-					0x68 =>  Zen2(0x68),
-					0x71 =>  Zen2(0x71),
-					0x90 =>  Zen2(0x90),
-					0x98 =>  Zen2(0x98),
+	/// Returns the execution profile of the model.
+	pub fn profile(&self) -> Profile {
+		self.profile
+	}
 
-					_ => Unknown(0x00),
-				},
+	/// Returns the LLVM CPU name of the model, as accepted by `-C target-cpu`.
+	pub fn llvm(&self) -> Option<&'static str> {
+		self.llvm
+	}
 
-				0x19 => match model {
-					0x01 =>  Zen3(0x01),
-					0x21 =>  Zen3(0x21),
-					0x30 =>  Zen3(0x30),
-					0x40 =>  Zen3(0x40),
-					0x50 =>  Zen3(0x50),
+	/// Returns `true` if the entry matches the given vendor and signature.
+	fn matches(&self, vendor: CPUVendor, signature: &Signature) -> bool {
+		let model = signature.model();
 
-					_ => Unknown(0x00),
-				},
+		let stepping = match (self.stepping, signature.stepping()) {
+			(None, _) => true,
+			(Some((lo, hi)), Some(s)) => (lo <= s) && (s <= hi),
+			(Some(_), None) => false,
+		};
 
-				_ => Unknown(0x00),
-			},
+		(self.vendor == vendor) && (self.family == signature.family()) && (self.model.0 <= model) && (model <= self.model.1) && stepping
+	}
+}
 
-			CPUVendor::Hygon => match family {
-				0x00 =>  Dhyana(0x00),
 
-				_ => Unknown(0x00),
-			},
 
-			_ => Unknown(0x00),
+impl CPUModel {
+	/// Decodes the model from the vendor and the vendor decoded signature.
+	/// Models shared by several products are told apart by the stepping.
+	pub fn from(vendor: CPUVendor, signature: Signature) -> CPUModel {
+		match MODELS.iter().find(|entry| entry.matches(vendor, &signature)) {
+			Some(entry) => (entry.build)(signature.model()),
+			_ => CPUModel::Unknown(0x00),
 		}
 	}
-}
 
+	/// Refines the model with the feature flags and the product brand of the CPU.
+	/// The flags are needed when the stepping is not known (e.g. a `06_55H`
+	/// signature), the brand when several products share a stepping.
+	pub fn refine(self, simd: &SIMDFlags, brand: &Brand) -> CPUModel {
+		use CPUModel::*;
 
+		let core = CoreNumber::parse(brand.as_bytes());
 
-impl core::fmt::Display for CPUModel {
-	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
-		use CPUModel::*;
+		match self {
+			SkyLake(0x55) if simd.avx512(SIMDFlags::AVX512BF16) => CooperLake(0x55),
+			SkyLake(0x55) if simd.avx512(SIMDFlags::AVX512VNNI) => CascadeLake(0x55),
 
-		let args = match *self {
-			Pentium5(n) => match n {
-				0x01 => "Pentium (800 nm)",
-				0x02 => "Pentium (600 nm | 350 nm)",
-				0x03 => "Pentium Overdrive (600 nm)",
-				0x04 => "Pentium MMX (800 nm)",
-
-				_ => "Inconsistent data",
-			},
-
-			Quark(n) => match n {
-				0x09 => "Quark",
-
-				_ => "Inconsistent data",
-			},
-
-			Pentium6(n) => match n {
-				0x01 => "Pentium Pro (350 nm)",
-				0x03 => "Pentium II (350 nm)\nPentium II Overdrive (250 nm)",
-				0x05 => "Pentium II (250 nm)\nPentium II Celeron (250 nm)\nPentium II Xeon (250 nm)",
-				0x06 => "Pentium II (250 nm)\nPentium II Celeron (250 nm)",
+			// 06_8EH steppings 9 and C are shared with Amber Lake (Core m3-8100Y, i5-10210Y).
+			KabyLake(0x8E) | CometLake(0x8E) if core.is_some_and(|c| (c.suffix == b'Y') && (c.generation() >= 8)) => AmberLake(0x8E),
 
-				0x07 => "Pentium III (250 nm)\nPentium III Xeon (250 nm)",
-				0x08 => "Pentium III (250 nm)\nPentium II Celeron (250 nm)\nPentium III Xeon (180 nm)",
-				0x0A => "Pentium III Xeon (180 nm)",
-				0x0B => "Pentium III (130 nm)\nPentium III Celeron (130 nm)",
-
-				_ => "Inconsistent data",
-			},
+			// 06_8EH stepping A is shared by Kaby Lake R and Coffee Lake-U (Core i5-8259U).
+			KabyLakeR(0x8E) if core.is_some_and(|c| (c.suffix == b'U') && (c.generation() == 8) && ((c.number % 10) == 9)) => CoffeeLake(0x8E),
 
-			Dothan(n) => match n {
-				0x09 => "Pentium M (130 nm)",
-				0x0D => "Pentium M (90 nm)",
-				0x15 => "Intel 80579 (90 nm)",
-
-				_ => "Inconsistent data",
-			},
+			// 06_8EH stepping C is shared by Comet Lake-U and Whiskey Lake-U (Core i7-8565U).
+			CometLake(0x8E) if core.is_some_and(|c| c.generation() == 8) => WhiskeyLake(0x8E),
 
-			Yonah(n) => match n {
-				0x0E => "Core Solo/Duo | Pentium Dual-Core T2xxx | Celeron M | Dual-Core Xeon (65 nm)",
+			model => model,
+		}
+	}
 
-				_ => "Inconsistent data",
-			},
+	/// Returns the model database.
+	pub fn entries() -> &'static [ModelEntry] {
+		&MODELS
+	}
 
-			Conroe(n) => match n {
-				0x0F => "Core 2 Duo  (65 nm)\nCore 2 Quad (65 nm)\nXeon (65 nm)",
-				0x16 => "Celeron (65 nm)\nCore 2 Duo (65 nm)",
-
-				_ => "Inconsistent data",
-			},
-
-			Penryn(n) => match n {
-				0x17 => "Core 2 Duo (45 nm)\nCore 2 Quad (45 nm)\nCore 2 Extreme (45 nm)\nXeon (45 nm)\nPentium Dual-Core (45 nm)",
-				0x1D => "Xeon (45 nm)",
-
-				_ => "Inconsistent data",
-			},
+	/// Returns the database entry of this model.
+	/// The rows of a variant and model number share their metadata whatever
+	/// the vendor, family and stepping: the build script checks it.
+	pub fn entry(&self) -> Option<&'static ModelEntry> {
+		let n = self.number();
 
-			Nehalem(n) => match n {
-				0x1A => "Core iX (45 nm)\nXeon (45 nm)",
-				0x1E => "Core iX (45 nm)",
-				0x1F => "Core iX (45 nm)",
-				0x2E => "Xeon (45 nm)",
-				0x25 => "Core iX (45 nm)",
-				0x2C => "Core iX (45 nm)\nXeon (45 nm)",
-				0x2F => "Xeon (45 nm)",
-
-				_ => "Inconsistent data",
-			},
+		MODELS.iter().find(|entry| ((entry.build)(n) == *self) && (entry.model.0 <= n) && (n <= entry.model.1))
+	}
 
-			SandyBridge(n) => match n {
-				0x2A => "Core iX (32 nm)",
-				0x2D => "Core iX (32 nm)\nXeon (32 nm)",
+	/// Returns the metadata of this model.
+	pub fn info(&self) -> Option<ModelInfo> {
+		self.entry().map(|entry| entry.info)
+	}
 
-				_ => "Inconsistent data",
-			},
+	/// Returns the microarchitecture of this model.
+	pub fn uarch(&self) -> Option<Uarch> {
+		self.info().map(|info| info.uarch())
+	}
 
-			IvyBridge(n) => match n {
-				0x3A => "Core iX (22 nm)",
-				0x3E => "Ivi Bridge-E (22 nm)",
+	/// Returns `true` if this model is of the same lineage as the given
+	/// microarchitecture and of the same generation or newer.
+	pub fn is_at_least(&self, uarch: Uarch) -> bool {
+		self.uarch().is_some_and(|u| u.is_at_least(uarch))
+	}
 
-				_ => "Inconsistent data",
-			},
+	/// Returns the class of the cores of this model.
+	pub fn class(&self) -> Option<CoreClass> {
+		self.uarch().map(|u| u.class())
+	}
 
-			Haswell(n) => match n {
-				0x3C => "Haswell (22 nm)",
-				0x3F => "Haswell-E (22 nm)",
-				0x45 => "Haswell-ULT (22 nm)",
-				0x46 => "Haswell [eDRAM] (22 nm)",
+	/// Returns the platform of this model.
+	pub fn platform(&self) -> Option<Platform> {
+		self.info().map(|info| info.platform())
+	}
 
-				_ => "Inconsistent data",
-			},
+	/// Returns the execution profile of this model.
+	/// Differs from the profile of its microarchitecture when a product line
+	/// has more or less execution units (e.g. server parts with AVX-512).
+	pub fn profile(&self) -> Option<Profile> {
+		self.entry().map(|entry| entry.profile)
+	}
 
-			Broadwell(n) => match n {
-				0x3D => "Broadwell-U (14 nm)",
-				0x47 => "Broadwell-H (14 nm)",
-				0x4F => "Broadwell-E (14 nm)",
-				0x56 => "Broadwell-DE (14 nm)",
+	/// Returns the LLVM CPU name of this model, as accepted by `-C target-cpu`.
+	pub fn llvm(&self) -> Option<&'static str> {
+		self.entry().and_then(|entry| entry.llvm)
+	}
+}
 
-				_ => "Inconsistent data",
-			},
 
-			SkyLake(n) => match n {
-				0x4E => "Sky Lake Client Y/U (14 nm)",
-				0x55 => "Sky/Cascade/Cooper Lake Server (14 nm)",
-				0x5E => "Sky Lake Client DT/H/S (14 nm)",
-				0x8E => "Kaby/Whiskey/Amber/Comet Lake Y/U (14 nm)",
-				0x9E => "Kaby/Coffee Lake DT/H/S (14 nm)",
-				0xA5 => "Comet Lake H/S (14 nm)",
-				0xA6 => "Comet Lake U/Y (14 nm)",
 
-				_ => "Inconsistent data"
-			},
+/// Processor number of a Core brand string (`i5-8259U`, `m3-8100Y`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CoreNumber {
+	/// Digits of the number.
+	number: u32,
 
-			PalmCove(n) => match n {
-				0x66 => "Cannon Lake (10 nm)",
+	/// Product line suffix (`U`, `Y`, `H`...), 0 if there is none.
+	suffix: u8,
+}
 
-				_ => "Inconsistent data",
-			},
 
-			SunnyCove(n) => match n {
-				0x6A => "Ice Lake-DE (10+ nm)",
-				0x6C => "Ice Lake-SP (10+ nm)",
-				0x7D => "Ice Lake-Y (10+ nm)",
-				0x7E => "Ice Lake-U (10+ nm)",
+impl CoreNumber {
+	/// Finds the first `<brand modifier>-<number><suffix>` word of the brand.
+	fn parse(brand: &[u8]) -> Option<CoreNumber> {
+		brand.split(|c| *c == b' ').find_map(|word| {
+			let rest = match word {
+				[b'i' | b'm', digit, b'-', rest @ ..] if digit.is_ascii_digit() => rest,
+				_ => return None,
+			};
 
-				_ => "Inconsistent data",
-			},
+			let digits = rest.iter().take_while(|c| c.is_ascii_digit()).count();
 
-			Bonnel(n) => match n {
-				0x1C => "Diamondville (45 nm)\nSilverthrone (45 nm)\nPineview (45 nm)",
-				0x26 => "Tunnel Creek (45 nm)",
+			if !(4..=5).contains(&digits) { return None; }
 
-				_ => "Inconsistent data",
-			},
+			let number = rest[..digits].iter().fold(0, |acc, c| (acc * 10) + u32::from(c - b'0'));
+			let suffix = rest.get(digits).copied().unwrap_or(0);
 
-			Saltwell(n) => match n {
-				0x27 => "Medfield (32 nm)",
-				0x35 => "Cloverview (32 nm)",
-				0x36 => "Cedarview (32 nm)\nCenterton (32 nm)",
+			Some( CoreNumber { number, suffix } )
+		})
+	}
 
-				_ => "Inconsistent data",
-			},
+	/// Returns the generation of the number: 8 for `8259U`, 10 for `10210Y`.
+	fn generation(&self) -> u32 {
+		self.number / 1000
+	}
+}
 
-			Silvermont(n) => match n {
-				0x37 => "Bay Trail (22 nm)",
-				0x4A => "Merrifield (22 nm)",
-				0x4D => "Avoton (22 nm)\nRangeley (22 nm)",
-				0x5A => "Moorefield (22 nm)",
-				0x5D => "SoFIA (22 nm)",
 
-				_ => "Inconsistent data",
-			},
 
-			Airmont(n) => match n {
-				0x4C => "Braswell (14 nm)\nCheery Trail (14 nm)",
-				0x75 => "Spreadtrum SC9853I-IA (14 nm)",
+impl Uarch {
+	/// Returns `true` if this microarchitecture is of the same lineage
+	/// as the given one and of the same generation or newer.
+	pub fn is_at_least(&self, other: Uarch) -> bool {
+		(self.lineage() == other.lineage()) && (self.generation() >= other.generation())
+	}
+}
 
-				_ => "Inconsistent data",
-			},
 
-			Goldmont(n) => match n {
-				0x5C => "Apollo Lake (14 nm)",
-				0x5F => "Denverton (14 nm)",
 
-				_ => "Inconsistent data",
-			},
+impl core::fmt::Display for Uarch {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		f.write_str(self.name())
+	}
+}
 
-			GoldmontPlus(n) => match n {
-				0x7A => "Gemini Lake (14 nm)",
 
-				_ => "Inconsistent data",
-			},
 
-			Willamette(n) => match n {
-				0x00 => "Pentium 4 Xeon (180 nm)",
-				0x01 => "Pentium 4 Celeron (180 nm)\nPentium 4 Xeon (180 nm)",
-				0x02 => "Pentium 4 (130 nm)\nPentium 4 EE (130 nm)\nPentium 4 Celeron (130 nm)\nPentium 4 Xeon (130 nm)",
+impl core::fmt::Display for CPUModel {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		match (*self, self.info()) {
+			(CPUModel::Unknown(_), _) => f.write_str("Model not known to this crate"),
 
-				_ => "Inconsistent data",
-			},
+			(_, Some(info)) => info.fmt(f),
 
-			Prescott(n) => match n {
-				0x03 => "Pentium 4 (90 nm)\nPentium 4 Xeon (90 nm)",
-				0x04 => "Pentium 4 (90 nm)\nPentium 4 EE (90 nm)\nPentium D (90 nm)\nCeleron D (90 nm)\nPentium 4 Xeon (90 nm)",
-				0x06 => "Pentium 4 (65 nm)\nPentium D EE (65 nm)\nCeleron D (65 nm)\nPentium 4 Xeon (65 nm)",
+			_ => f.write_str("Inconsistent data"),
+		}
+	}
+}
 
-				_ => "Inconsistent data",
-			},
 
 
-			KnightsLanding(n) => match n {
-				0x57 => "Knights Landing (14 nm)",
+#[cfg(test)]
+mod tests {
+	use super::*;
 
-				_ => "Inconsistent data",
-			},
+	use crate::cpuid::{ CPUID, Registers };
+	use crate::info::Model;
 
-			KnightsMill(n) => match n {
-				0x85 => "Knights Mill (14 nm)",
+	/// Builds a dump with the given signature, leaf 7 ECX, sub-leaf 1 EAX and product brand.
+	fn dump(eax: u32, ecx: u32, bf16: u32, brand: &str) -> CPUID {
+		let mut cpuid = CPUID::empty();
 
-				_ => "Inconsistent data",
-			},
+		cpuid.insert(0x00, 0, Registers { eax: 0x07, ebx: 0x756E6547, ecx: 0x6C65746E, edx: 0x49656E69 });
+		cpuid.insert(0x01, 0, Registers { eax, ..Registers::default() });
+		cpuid.insert(0x07, 0, Registers { eax: 0x01, ecx, ..Registers::default() });
+		cpuid.insert(0x07, 1, Registers { eax: bf16, ..Registers::default() });
 
+		cpuid.insert(0x80000000, 0, Registers { eax: 0x80000004, ..Registers::default() });
 
+		let mut bytes = [0u8; 48];
+		bytes[..brand.len()].copy_from_slice(brand.as_bytes());
 
-			// AMD
-			K5(n) => match n {
-				0x00 | 0x01 | 0x02 => "K5 (500 nm - 350 nm)",
+		for (i, leaf) in bytes.chunks(16).enumerate() {
+			let r = |n: usize| u32::from_le_bytes([leaf[n], leaf[n + 1], leaf[n + 2], leaf[n + 3]]);
 
-				_ => "Inconsistent data",
-			},
+			cpuid.insert(0x80000002 + i as u32, 0, Registers { eax: r(0), ebx: r(4), ecx: r(8), edx: r(12) });
+		}
 
-			K6(n) => match n {
-				0x06 | 0x07 | 0x08 | 0x0D => "K6 (350 nm - 250 nm)",
+		cpuid
+	}
 
-				_ => "Inconsistent data",
-			},
+	fn refine(model: CPUModel, ecx: u32, bf16: u32, brand: &str) -> CPUModel {
+		let cpuid = dump(0, ecx, bf16, brand);
 
-			Geode(n) => match n {
-				0x0A => "Geode",
+		model.refine(&SIMDFlags::from(&cpuid), &Brand::product(&cpuid))
+	}
 
-				_ => "Inconsistent data",
-			},
+	#[test]
+	fn stepping() {
+		let intel = |model, stepping| CPUModel::from(CPUVendor::Intel, Signature::new(0x06, model, stepping));
 
-			K7(n) => match n {
-				0x06 => "K7 (250 nm - 14 nm)",
+		assert_eq!(intel(0x9E, Some(0x09)), CPUModel::KabyLake(0x9E));
+		assert_eq!(intel(0x9E, Some(0x0A)), CPUModel::CoffeeLake(0x9E));
+		assert_eq!(intel(0x9E, None), CPUModel::SkyLake(0x9E));
 
-				_ => "Inconsistent data",
-			},
+		assert_eq!(intel(0x8E, Some(0x09)), CPUModel::KabyLake(0x8E));
+		assert_eq!(intel(0x8E, Some(0x0A)), CPUModel::KabyLakeR(0x8E));
+		assert_eq!(intel(0x8E, Some(0x0B)), CPUModel::WhiskeyLake(0x8E));
+		assert_eq!(intel(0x8E, Some(0x0C)), CPUModel::CometLake(0x8E));
 
-			K8(n) => match n {
-				0x0F => "K8 (130 nm - 65 nm)",
-				0x11 => "K8 (90 nm - 65 nm)",
+		assert_eq!(intel(0x55, Some(0x04)), CPUModel::SkyLake(0x55));
+		assert_eq!(intel(0x55, Some(0x07)), CPUModel::CascadeLake(0x55));
+		assert_eq!(intel(0x55, Some(0x0B)), CPUModel::CooperLake(0x55));
 
-				_ => "Inconsistent data",
-			},
+		// Each product has a single codename, an unknown stepping none.
+		assert_eq!(CPUModel::KabyLakeR(0x8E).info().map(|i| i.codenames()), Some(&["Kaby Lake R"][..]));
+		assert_eq!(intel(0x8E, None).info().map(|i| i.codenames()), Some(&[][..]));
+	}
 
-			K10(n) => match n {
-				0x10 => "K10 Opteron\nK10 Phenom\nK10 Athlon\nK10 Sempron",
-				0x12 => "K10 Llano APU",
+	#[test]
+	fn features() {
+		const VNNI: u32 = 1 << 11;
+		const BF16: u32 = 1 << 5;
 
-				_ => "Inconsistent data",
-			},
+		let skylake = CPUModel::SkyLake(0x55);
 
-			Bobcat(n) => match n {
-				0x14 => "Bobcat",
+		assert_eq!(refine(skylake, 0, 0, ""), CPUModel::SkyLake(0x55));
+		assert_eq!(refine(skylake, VNNI, 0, ""), CPUModel::CascadeLake(0x55));
+		assert_eq!(refine(skylake, VNNI, BF16, ""), CPUModel::CooperLake(0x55));
+	}
 
-				_ => "Inconsistent data",
-			},
+	#[test]
+	fn brand() {
+		let kabylake = CPUModel::KabyLake(0x8E);
+		let kabylaker = CPUModel::KabyLakeR(0x8E);
+		let cometlake = CPUModel::CometLake(0x8E);
 
-			Bulldozer(n) => match n {
-				0x00 => "Bulldozer (Engineer sample)",
-				0x01 => "Bulldozer Zambezi\nBulldozer Interlagos",
+		assert_eq!(refine(kabylake, 0, 0, "Intel(R) Core(TM) i7-7500U CPU @ 2.70GHz"), kabylake);
+		assert_eq!(refine(kabylake, 0, 0, "Intel(R) Core(TM) m3-8100Y CPU @ 1.10GHz"), CPUModel::AmberLake(0x8E));
 
-				_ => "Inconsistent data",
-			},
+		assert_eq!(refine(kabylaker, 0, 0, "Intel(R) Core(TM) i5-8250U CPU @ 1.60GHz"), kabylaker);
+		assert_eq!(refine(kabylaker, 0, 0, "Intel(R) Core(TM) i5-8259U CPU @ 2.30GHz"), CPUModel::CoffeeLake(0x8E));
 
-			Piledriver(n) => match n {
-				0x02 => "Piledriver Vishera",
-				0x10 => "Piledriver Trinity",
-				0x13 => "Piledriver Richland",
+		assert_eq!(refine(cometlake, 0, 0, "Intel(R) Core(TM) i7-10510U CPU @ 1.80GHz"), cometlake);
+		assert_eq!(refine(cometlake, 0, 0, "Intel(R) Core(TM) i7-8565U CPU @ 1.80GHz"), CPUModel::WhiskeyLake(0x8E));
+		assert_eq!(refine(cometlake, 0, 0, "Intel(R) Core(TM) i5-10210Y CPU @ 1.00GHz"), CPUModel::AmberLake(0x8E));
 
-				_ => "Inconsistent data",
-			},
+		// Without a brand the stepping decides.
+		assert_eq!(refine(kabylaker, 0, 0, ""), kabylaker);
+		assert_eq!(refine(kabylaker, 0, 0, "Intel(R) Pentium(R) CPU 4415U @ 2.30GHz"), kabylaker);
+	}
 
-			Steamroller(n) => match n {
-				0x38 => "Steamroller Godavari",
-				0x30 => "Steamroller Kaveri",
+	#[test]
+	fn live() {
+		// A host dump always has a stepping and a brand: `Model::from` refines it.
+		let model = Model::from(&dump(0x000806EA, 0, 0, "Intel(R) Core(TM) i5-8259U CPU @ 2.30GHz"));
 
-				_ => "Inconsistent data",
-			},
+		assert_eq!(model.stepping(), 0x0A);
+		assert_eq!(model.model(), CPUModel::CoffeeLake(0x8E));
+		assert_eq!(model.model().info().map(|i| i.codenames()), Some(&["Coffee Lake-U"][..]));
 
-			Excavator(n) => match n {
-				0x60 => "Excavator Carrizo",
-				0x65 => "Excavator Bristol Ridge",
-				0x70 => "Excavator Stone Ridge",
+		let model = Model::from(&dump(0x000806EA, 0, 0, "Intel(R) Core(TM) i7-8650U CPU @ 1.90GHz"));
 
-				_ => "Inconsistent data",
-			},
+		assert_eq!(model.model(), CPUModel::KabyLakeR(0x8E));
+	}
 
-			Puma(n) => match n {
-				0..=2 => "Inconsistent data",
-				_ => "Puma",
-			},
+	#[test]
+	fn hybrid() {
+		let intel = |model| CPUModel::from(CPUVendor::Intel, Signature::new(0x06, model, Some(0x02)));
 
-			Jaguar(n) => match n {
-				0..=2 => "Jaguar",
-				_ => "Inconsistent data",
-			},
+		let alderlake = intel(0x97).info().unwrap();
+		assert_eq!((alderlake.uarch(), alderlake.secondary()), (Uarch::GoldenCove, Some(Uarch::Gracemont)));
 
-			Zen(n) => match n {
-				0x01 => "Zen  - Ryzen 1000 (14 nm)\n[EPYC Server] 'Naples'\n[Threadripper CPU] 'Whitehaven'\n[Desktop CPU] 'Summit ridge'\n[Embedded Server] 'Snowy Owl'",
-				0x08 => "Zen+ - Ryzen 2000 (12 nm)\n[Desktop CPU] 'Pinnacle Ridge'\n[Threadripper CPU] 'Colfax'",
-				0x11 => "Zen  - Ryzen 1000 (14 nm)\n[Desktop APU] 'Raven Ridge'\n[Mobile APU] 'Raven Ridge'\n[Embedded APU] 'Great Horned Owl'",
-				0x18 => "Zen+ - Ryzen 2000 (12 nm)\n[Desktop APU] 'Picasso'\n[Mobile APU] 'Picasso'",
+		let lunarlake = intel(0xBD).info().unwrap();
+		assert_eq!((lunarlake.uarch(), lunarlake.secondary()), (Uarch::LionCove, Some(Uarch::Skymont)));
 
-				_ => "Inconsistent data",
-			},
+		assert_eq!(intel(0x97).class(), Some(CoreClass::Big));
+		assert_eq!(Uarch::Skymont.class(), CoreClass::Small);
+		assert!(Uarch::Skymont.is_at_least(Uarch::Gracemont));
 
-			Zen2(n) => match n {
-				0x31 => "Zen 2 - Ryzen 3000 (7 nm)\n[EPYC Server] 'Rome'",
-				0x60 => "Zen 2 - Ryzen 3000 (7 nm)\n[Desktop APU] 'Renoir'\n[Mobile APU] 'Renoir'\n[Threadripper CPU] 'Castle Peak'",
-				0x68 => "Zen 2 - Ryzen 5000 (7 nm)\n[Mobile APU] 'Lucienne'",
-				0x71 => "Zen 2 - Ryzen 3000 (7 nm)\n[Desktop CPU] 'Matisse'",
-				0x90 => "Zen 2 - Ryzen X000 (7 nm)\n[Desktop APU] 'Van Gogh'",
-				0x98 => "Zen 2 - Ryzen X000 (7 nm)\n[Mobile APU] 'Mero'",
+		// Hybrid models, and only them, have a second kind of cores.
+		for entry in CPUModel::entries() {
+			let info = entry.info();
 
-				_ => "Inconsistent data",
-			},
+			assert_eq!(info.core() == CoreType::Hybrid, info.secondary().is_some(), "{:?}", entry.model());
+		}
+	}
 
-			Zen3(n) => match n {
-				0x01 => "Zen 3 - Ryzen X000 (7 nm)\n[Threadripper CPU] 'Genesis Peak'",
-				0x21 => "Zen 3 - Ryzen 5000 (7 nm)\n[Desktop CPU] 'Vermeer'",
-				0x30 => "Zen 3 - Ryzen X000 (7 nm)\n[] 'Badami''Trento'",
-				0x40 => "Zen 3 - Ryzen 5000 (7 nm)\n[Desktop APU] 'Rembrandt'",
-				0x50 => "Zen 3 - Ryzen 5000 (7 nm)\n[Mobile APU] 'Cezanne'",
+	#[test]
+	fn hygon() {
+		let hygon = |model| CPUModel::from(CPUVendor::Hygon, Signature::new(0x18, model, Some(0x01))).uarch();
 
-				_ => "Inconsistent data",
-			},
+		assert_eq!(hygon(0x01), Some(Uarch::Dhyana));
+		assert_eq!(hygon(0x02), Some(Uarch::Hygon2G));
+		assert_eq!(hygon(0x04), Some(Uarch::Hygon3G));
 
-			Dhyana(n) => match n {
-				0x00 => "Dhyana",
+		// The vendor is part of the key: family 0x18 is not an AMD family.
+		assert_eq!(CPUModel::from(CPUVendor::AMD, Signature::new(0x18, 0x02, None)), CPUModel::Unknown(0x00));
 
-				_ => "Inconsistent data",
-			},
+		assert!(Uarch::Hygon3G.is_at_least(Uarch::Dhyana));
+	}
 
-			Unknown(n) => match n {
-				0x00 => "Unknown architecture",
+	#[test]
+	fn table() {
+		for entry in CPUModel::entries() {
+			let (lo, hi) = entry.models();
+			assert!(lo <= hi, "{:?}", entry.model());
+
+			if let Some((lo, hi)) = entry.steppings() {
+				assert!(lo <= hi, "{:?}", entry.model());
+			}
+
+			// The metadata of the variant is the metadata of the row.
+			let model = entry.model();
+			assert_eq!(model.info(), Some(entry.info()), "{:?}", model);
+			assert_eq!(model.profile(), Some(entry.profile()), "{:?}", model);
+			assert_eq!(model.llvm(), entry.llvm(), "{:?}", model);
+		}
+	}
 
-				_ => "Inconsistent data",
-			},
-		};
+	#[test]
+	fn family() {
+		// Model 4 of Cyrix families 4 and 5 are two products.
+		let cx5510 = CPUModel::from(CPUVendor::Cyrix, Signature::new(0x04, 0x04, Some(0x00)));
+		let gxm = CPUModel::from(CPUVendor::Cyrix, Signature::new(0x05, 0x04, Some(0x04)));
 
-		f.write_str(args)
+		assert_eq!(cx5510.info().map(|i| i.codenames()), Some(&["Cx5510"][..]));
+		assert_eq!(gxm.info().map(|i| i.codenames()), Some(&["GXm"][..]));
+		assert_ne!(cx5510.info().and_then(|i| i.year()), gxm.info().and_then(|i| i.year()));
 	}
 }
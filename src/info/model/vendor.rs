@@ -44,13 +44,13 @@ pub enum CPUVendor {
 	/// United Microelectronics Corporation.
 	UMC,
 
-	/// 
+	/// RDC Semiconductor.
 	RDC,
 
 	/// DM&P Electronics.
 	DMP,
 
-	// Shangai Zhaoxin Semiconductor (Zhaozin).
+	/// Shanghai Zhaoxin Semiconductor.
 	Zhaoxin,
 
 	/// Elbrus.
@@ -111,19 +111,19 @@ impl core::fmt::Display for CPUVendor {
 		let args = match *self {
 			Intel     => "Intel",
 			AMD       => "AMD",
-			Centaur   =>"Centaur",
+			Centaur   => "Centaur Technology",
 			Hygon     => "Hygon",
-			Transmeta => "Transmeta",
-			Cyrix     => "Cyrix",
-			Rise      => "",
-			NSC       => "National Semiconductors",
+			Transmeta => "Transmeta Corporation",
+			Cyrix     => "Cyrix Corporation",
+			Rise      => "Rise Technology",
+			NSC       => "National Semiconductor",
 			SIS       => "Silicon Integrated Systems",
 			NexGen    => "NexGen",
-			UMC       => "",
-			RDC       => "",
-			DMP       => "DM&P",
+			UMC       => "United Microelectronics Corporation",
+			RDC       => "RDC Semiconductor",
+			DMP       => "DM&P Electronics",
 			Zhaoxin   => "Zhaoxin",
-			Elbrus    => "Elbrus",
+			Elbrus    => "MCST Elbrus",
 			Unknown   => "Unknown",
 		};
 
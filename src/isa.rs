@@ -0,0 +1,202 @@
+//! ISA Flags of the host architecture.
+//! Non SIMD instruction set extensions.
+
+
+// This Source Code Form is subject to the terms of the
+// Mozilla Public License, v. 2.0. If a copy of the MPL
+// was not distributed with this file, You can obtain one
+// at https://mozilla.org/MPL/2.0/.
+
+
+
+use crate::cpuid::CPUID;
+use crate::error::Error;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ISAFlags {
+	/// ISA flags.
+	isa: u32,
+}
+
+
+
+impl ISAFlags {
+	// ISA Flags.
+	// ****************************************************
+
+	/// x87 FPU on chip.
+	pub const FPU     : u32 = 1 << 0;
+
+	/// CMPXCHG8B instruction.
+	pub const CX8     : u32 = 1 << 1;
+
+	/// CMOV instructions.
+	pub const CMOV    : u32 = 1 << 2;
+
+	/// FXSAVE and FXRSTOR instructions.
+	pub const FXSR    : u32 = 1 << 3;
+
+	/// SYSCALL and SYSRET instructions.
+	pub const SYSCALL : u32 = 1 << 4;
+
+	/// 64-bit long mode.
+	pub const LM      : u32 = 1 << 5;
+
+
+	/// CMPXCHG16B instruction.
+	pub const CX16    : u32 = 1 << 8;
+
+	/// LAHF and SAHF instructions in 64-bit mode.
+	pub const LAHF    : u32 = 1 << 9;
+
+	/// POPCNT instruction.
+	pub const POPCNT  : u32 = 1 << 10;
+
+
+	/// MOVBE instruction.
+	pub const MOVBE   : u32 = 1 << 16;
+
+	/// Bit Manipulation Instruction set 1.
+	pub const BMI1    : u32 = 1 << 17;
+
+	/// Bit Manipulation Instruction set 2.
+	pub const BMI2    : u32 = 1 << 18;
+
+	/// LZCNT instruction (ABM).
+	pub const LZCNT   : u32 = 1 << 19;
+
+	/// Half precision conversion instructions.
+	pub const F16C    : u32 = 1 << 20;
+
+
+	/// AES instructions.
+	pub const AES       : u32 = 1 << 24;
+
+	/// PCLMULQDQ carry-less multiplication instruction.
+	pub const PCLMULQDQ : u32 = 1 << 25;
+
+	/// SHA-1 and SHA-256 instructions.
+	pub const SHA       : u32 = 1 << 26;
+
+	/// RDRAND instruction.
+	pub const RDRAND    : u32 = 1 << 27;
+
+	/// RDSEED instruction.
+	pub const RDSEED    : u32 = 1 << 28;
+
+	/// ADCX and ADOX instructions.
+	pub const ADX       : u32 = 1 << 29;
+
+	/// CLFLUSHOPT instruction.
+	pub const CLFLUSHOPT : u32 = 1 << 30;
+
+	// ****************************************************
+
+
+
+
+	/// Returns `true` if the ISA flag given is present.
+	#[inline(always)]
+	pub fn isa(&self, f: u32) -> bool {
+		(self.isa & f) != 0
+	}
+
+
+
+	/// Returns the ISA flags of the host, read once per process.
+	pub fn read() -> ISAFlags {
+		crate::cache::get().isa()
+	}
+
+	/// Returns the ISA flags of the host, or `Error::CpuidUnavailable`.
+	pub fn try_read() -> Result<ISAFlags, Error> {
+		Ok( crate::cache::try_get()?.isa() )
+	}
+
+	/// Decodes the ISA flags from a CPUID dump.
+	pub fn from(cpuid: &CPUID) -> ISAFlags {
+		ISAFlags { isa: Self::inner(cpuid) }
+	}
+
+
+	/// Performs CPUID checks for ISA features.
+	fn inner(cpuid: &CPUID) -> u32 {
+		let mut isa = 0;
+
+		// CPUID 0x00000001
+		if let Some(info) = cpuid.leaf(0x00000001) {
+
+			if ( info.edx        & 1) == 1 { isa |= Self::FPU    }
+			if ((info.edx >>  8) & 1) == 1 { isa |= Self::CX8    }
+			if ((info.edx >> 15) & 1) == 1 { isa |= Self::CMOV   }
+			if ((info.edx >> 24) & 1) == 1 { isa |= Self::FXSR   }
+
+			if ((info.ecx >>  1) & 1) == 1 { isa |= Self::PCLMULQDQ }
+			if ((info.ecx >> 13) & 1) == 1 { isa |= Self::CX16      }
+			if ((info.ecx >> 22) & 1) == 1 { isa |= Self::MOVBE     }
+			if ((info.ecx >> 23) & 1) == 1 { isa |= Self::POPCNT    }
+			if ((info.ecx >> 25) & 1) == 1 { isa |= Self::AES       }
+			if ((info.ecx >> 29) & 1) == 1 { isa |= Self::F16C      }
+			if ((info.ecx >> 30) & 1) == 1 { isa |= Self::RDRAND    }
+		}
+
+		// CPUID 0x00000007
+		if let Some(info) = cpuid.leaf(0x00000007) {
+			if ((info.ebx >>  3) & 1) == 1 { isa |= Self::BMI1       }
+			if ((info.ebx >>  8) & 1) == 1 { isa |= Self::BMI2       }
+			if ((info.ebx >> 18) & 1) == 1 { isa |= Self::RDSEED     }
+			if ((info.ebx >> 19) & 1) == 1 { isa |= Self::ADX        }
+			if ((info.ebx >> 23) & 1) == 1 { isa |= Self::CLFLUSHOPT }
+			if ((info.ebx >> 29) & 1) == 1 { isa |= Self::SHA        }
+		}
+
+		// CPUID 0x80000001
+		if let Some(info) = cpuid.leaf(0x80000001) {
+
+			if ( info.ecx        & 1) == 1 { isa |= Self::LAHF    }
+			if ((info.ecx >>  5) & 1) == 1 { isa |= Self::LZCNT   }
+
+			if ((info.edx >> 11) & 1) == 1 { isa |= Self::SYSCALL }
+			if ((info.edx >> 29) & 1) == 1 { isa |= Self::LM      }
+		}
+
+		isa
+	}
+}
+
+impl core::fmt::Display for ISAFlags {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		const NAMES: [(u32, &str); 21] = [
+			(ISAFlags::FPU,        "FPU"),
+			(ISAFlags::CX8,        "CX8"),
+			(ISAFlags::CMOV,       "CMOV"),
+			(ISAFlags::FXSR,       "FXSR"),
+			(ISAFlags::SYSCALL,    "SYSCALL"),
+			(ISAFlags::LM,         "LM"),
+			(ISAFlags::CX16,       "CX16"),
+			(ISAFlags::LAHF,       "LAHF"),
+			(ISAFlags::POPCNT,     "POPCNT"),
+			(ISAFlags::MOVBE,      "MOVBE"),
+			(ISAFlags::BMI1,       "BMI1"),
+			(ISAFlags::BMI2,       "BMI2"),
+			(ISAFlags::LZCNT,      "LZCNT"),
+			(ISAFlags::F16C,       "F16C"),
+			(ISAFlags::AES,        "AES"),
+			(ISAFlags::PCLMULQDQ,  "PCLMULQDQ"),
+			(ISAFlags::SHA,        "SHA"),
+			(ISAFlags::RDRAND,     "RDRAND"),
+			(ISAFlags::RDSEED,     "RDSEED"),
+			(ISAFlags::ADX,        "ADX"),
+			(ISAFlags::CLFLUSHOPT, "CLFLUSHOPT"),
+		];
+
+		f.write_str("CPU ISA features\n")?;
+
+		for (_, name) in NAMES.iter().filter(|(flag, _)| self.isa(*flag)) {
+			writeln!(f, "  {}", name)?;
+		}
+
+		Ok(())
+	}
+}
@@ -0,0 +1,273 @@
+//! x86-64 psABI microarchitecture levels.
+//! Computed from the ISA and SIMD flags of the host.
+
+
+// This Source Code Form is subject to the terms of the
+// Mozilla Public License, v. 2.0. If a copy of the MPL
+// was not distributed with this file, You can obtain one
+// at https://mozilla.org/MPL/2.0/.
+
+
+
+use crate::isa::ISAFlags;
+use crate::simd::SIMDFlags;
+
+
+
+/// Feature required by a level: psABI name and check.
+type Feature = (&'static str, fn(&ISAFlags, &SIMDFlags) -> bool);
+
+
+/// Features of x86-64 (baseline).
+/// OSFXSR can not be read from user space, it is assumed with FXSR and SSE.
+const V1: [Feature; 9] = [
+	("CMOV",   |isa, _| isa.isa(ISAFlags::CMOV)),
+	("CX8",    |isa, _| isa.isa(ISAFlags::CX8)),
+	("FPU",    |isa, _| isa.isa(ISAFlags::FPU)),
+	("FXSR",   |isa, _| isa.isa(ISAFlags::FXSR)),
+	("MMX",    |_, simd| simd.simd(SIMDFlags::MMX)),
+	("OSFXSR", |isa, simd| isa.isa(ISAFlags::FXSR) && simd.simd(SIMDFlags::SSE1)),
+	("SCE",    |isa, _| isa.isa(ISAFlags::SYSCALL)),
+	("SSE",    |_, simd| simd.simd(SIMDFlags::SSE1)),
+	("SSE2",   |_, simd| simd.simd(SIMDFlags::SSE2)),
+];
+
+/// Features added by x86-64-v2.
+const V2: [Feature; 7] = [
+	("CMPXCHG16B", |isa, _| isa.isa(ISAFlags::CX16)),
+	("LAHF-SAHF",  |isa, _| isa.isa(ISAFlags::LAHF)),
+	("POPCNT",     |isa, _| isa.isa(ISAFlags::POPCNT)),
+	("SSE3",       |_, simd| simd.simd(SIMDFlags::SSE3)),
+	("SSE4_1",     |_, simd| simd.simd(SIMDFlags::SSE41)),
+	("SSE4_2",     |_, simd| simd.simd(SIMDFlags::SSE42)),
+	("SSSE3",      |_, simd| simd.simd(SIMDFlags::SSSE3)),
+];
+
+/// Features added by x86-64-v3.
+/// AVX is only reported by `SIMDFlags` when the OS saves the YMM state.
+const V3: [Feature; 9] = [
+	("AVX",     |_, simd| simd.simd(SIMDFlags::AVX)),
+	("AVX2",    |_, simd| simd.simd(SIMDFlags::AVX2)),
+	("BMI1",    |isa, _| isa.isa(ISAFlags::BMI1)),
+	("BMI2",    |isa, _| isa.isa(ISAFlags::BMI2)),
+	("F16C",    |isa, _| isa.isa(ISAFlags::F16C)),
+	("FMA",     |_, simd| simd.simd(SIMDFlags::FMA3)),
+	("LZCNT",   |isa, _| isa.isa(ISAFlags::LZCNT)),
+	("MOVBE",   |isa, _| isa.isa(ISAFlags::MOVBE)),
+	("OSXSAVE", |_, simd| simd.simd(SIMDFlags::OSXSAVE)),
+];
+
+/// Features added by x86-64-v4.
+/// The AVX-512 extensions need the OS to save the ZMM state.
+const V4: [Feature; 5] = [
+	("AVX512F",  |_, simd| simd.simd(SIMDFlags::AVX512) && simd.avx512(SIMDFlags::AVX512F)),
+	("AVX512BW", |_, simd| simd.simd(SIMDFlags::AVX512) && simd.avx512(SIMDFlags::AVX512BW)),
+	("AVX512CD", |_, simd| simd.simd(SIMDFlags::AVX512) && simd.avx512(SIMDFlags::AVX512CD)),
+	("AVX512DQ", |_, simd| simd.simd(SIMDFlags::AVX512) && simd.avx512(SIMDFlags::AVX512DQ)),
+	("AVX512VL", |_, simd| simd.simd(SIMDFlags::AVX512) && simd.avx512(SIMDFlags::AVX512VL)),
+];
+
+
+
+/// x86-64 psABI microarchitecture level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum X86Level {
+	/// x86-64 baseline.
+	V1,
+
+	/// x86-64-v2: CMPXCHG16B, LAHF-SAHF, POPCNT and SSE4.2.
+	V2,
+
+	/// x86-64-v3: AVX2, BMI1, BMI2, F16C, FMA, LZCNT and MOVBE.
+	V3,
+
+	/// x86-64-v4: AVX-512 F, BW, CD, DQ and VL.
+	V4,
+}
+
+
+impl X86Level {
+	/// All the levels, from the lowest to the highest.
+	pub const ALL: [X86Level; 4] = [X86Level::V1, X86Level::V2, X86Level::V3, X86Level::V4];
+
+
+	/// Detects the level of the host.
+	pub fn detect() -> LevelReport {
+		let host = crate::cache::get();
+
+		Self::check(&host.isa(), &host.simd())
+	}
+
+	/// Computes the level met by the given flags.
+	pub fn check(isa: &ISAFlags, simd: &SIMDFlags) -> LevelReport {
+		let mut level = None;
+
+		for next in Self::ALL.iter() {
+			let missing = next.features().iter()
+				.enumerate()
+				.filter(|(_, (_, check))| !check(isa, simd))
+				.fold(0u32, |acc, (i, _)| acc | (1 << i));
+
+			if missing != 0 {
+				return LevelReport { level, next: Some(*next), missing };
+			}
+
+			level = Some(*next);
+		}
+
+		LevelReport { level, next: None, missing: 0 }
+	}
+
+	/// Returns the name of the level (`x86-64-v3`).
+	pub fn name(&self) -> &'static str {
+		match *self {
+			X86Level::V1 => "x86-64",
+			X86Level::V2 => "x86-64-v2",
+			X86Level::V3 => "x86-64-v3",
+			X86Level::V4 => "x86-64-v4",
+		}
+	}
+
+	/// Returns the psABI names of the features added by the level.
+	pub fn requirements(&self) -> impl Iterator<Item = &'static str> {
+		self.features().iter().map(|(name, _)| *name)
+	}
+
+	/// Returns the features added by the level.
+	fn features(&self) -> &'static [Feature] {
+		match *self {
+			X86Level::V1 => &V1,
+			X86Level::V2 => &V2,
+			X86Level::V3 => &V3,
+			X86Level::V4 => &V4,
+		}
+	}
+}
+
+
+impl core::fmt::Display for X86Level {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		f.write_str(self.name())
+	}
+}
+
+
+
+/// Level met by a CPU and the features missing for the next one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LevelReport {
+	/// Highest level met. `None` if not even the baseline is met.
+	level: Option<X86Level>,
+
+	/// Next level, `None` if the highest level is met.
+	next: Option<X86Level>,
+
+	/// Missing features of the next level, as indices in its feature list.
+	missing: u32,
+}
+
+
+impl LevelReport {
+	/// Returns the highest level met.
+	pub fn level(&self) -> Option<X86Level> {
+		self.level
+	}
+
+	/// Returns the next level, not met.
+	pub fn next(&self) -> Option<X86Level> {
+		self.next
+	}
+
+	/// Returns the psABI names of the features missing for the next level.
+	pub fn missing(&self) -> impl Iterator<Item = &'static str> {
+		let missing = self.missing;
+
+		self.next.into_iter()
+			.flat_map(|next| next.requirements().enumerate())
+			.filter(move |(i, _)| ((missing >> i) & 1) == 1)
+			.map(|(_, name)| name)
+	}
+}
+
+
+impl core::fmt::Display for LevelReport {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		match self.level {
+			Some(level) => write!(f, "{}", level)?,
+			None => f.write_str("Below x86-64")?,
+		}
+
+		if let Some(next) = self.next {
+			write!(f, " ({} missing:", next)?;
+
+			for name in self.missing() {
+				write!(f, " {}", name)?;
+			}
+
+			f.write_str(")")?;
+		}
+
+		Ok(())
+	}
+}
+
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use crate::cpuid::CPUID;
+
+	use std::{ format, vec::Vec };
+
+	/// Core i7-8700K (Coffee Lake), with AVX enabled by the OS.
+	const COFFEELAKE: &str = "
+		0x00000000 0x00: eax=0x00000016 ebx=0x756e6547 ecx=0x6c65746e edx=0x49656e69
+		0x00000001 0x00: eax=0x000906ea ebx=0x00100800 ecx=0x7ffafbbf edx=0xbfebfbff
+		0x00000007 0x00: eax=0x00000000 ebx=0x029c6fbf ecx=0x40000000 edx=0xbc000400
+		0x80000000 0x00: eax=0x80000008 ebx=0x00000000 ecx=0x00000000 edx=0x00000000
+		0x80000001 0x00: eax=0x00000000 ebx=0x00000000 ecx=0x00000121 edx=0x2c100800
+		xcr0=0x000000000000001f
+	";
+
+	fn check(dump: &str) -> LevelReport {
+		let cpuid: CPUID = dump.parse().unwrap();
+
+		X86Level::check(&ISAFlags::from(&cpuid), &SIMDFlags::from(&cpuid))
+	}
+
+	#[test]
+	fn coffeelake() {
+		let report = check(COFFEELAKE);
+
+		assert_eq!(report.level(), Some(X86Level::V3));
+		assert_eq!(report.next(), Some(X86Level::V4));
+		assert_eq!(report.missing().collect::<Vec<_>>(), X86Level::V4.requirements().collect::<Vec<_>>());
+	}
+
+	#[test]
+	fn os_support() {
+		// Without the YMM state enabled AVX is not usable.
+		let report = check(&COFFEELAKE.replace("xcr0=0x000000000000001f", "xcr0=0x0000000000000003"));
+
+		assert_eq!(report.level(), Some(X86Level::V2));
+		assert!(report.missing().any(|name| name == "AVX"));
+	}
+
+	#[test]
+	fn empty() {
+		let report = check("");
+
+		assert_eq!(report.level(), None);
+		assert_eq!(report.next(), Some(X86Level::V1));
+		assert_eq!(report.missing().count(), X86Level::V1.requirements().count());
+		assert!(format!("{}", report).starts_with("Below x86-64"));
+	}
+
+	#[test]
+	#[cfg(target_arch = "x86_64")]
+	fn detect() {
+		assert!(X86Level::detect().level() >= Some(X86Level::V1));
+	}
+}
@@ -1,4 +1,7 @@
 //! CPU flags of the native host.
+//!
+//! The crate is `no_std`. The `alloc` feature adds conversions to owned
+//! strings and the `std` feature reads the microcode revision from the OS.
 
 // This Source Code Form is subject to the terms of the
 // Mozilla Public License, v. 2.0. If a copy of the MPL
@@ -6,8 +9,72 @@
 // at https://mozilla.org/MPL/2.0/.
 
 
+#![no_std]
+
+// `__cpuid` is safe on recent toolchains but not on older ones.
+#![allow(unused_unsafe)]
+
+// Vendor and feature names follow their official capitalization.
+#![allow(clippy::upper_case_acronyms)]
+
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(any(test, feature = "std"))]
+extern crate std;
+
+
+pub mod cache;
+
+pub mod cpuid;
+
+pub mod dispatch;
+
+pub mod error;
+
+pub mod feature;
+
 pub mod info;
 
+pub mod isa;
+
+pub mod level;
+
 pub mod prelude;
 
-pub mod simd;
\ No newline at end of file
+pub mod simd;
+
+pub mod target;
+
+pub mod tier;
+
+
+pub use crate::cache::{ get, try_get };
+
+pub use crate::error::Error;
+
+
+/// Feature sets are checked when the function is compiled. Unknown features
+/// are rejected:
+///
+/// ```compile_fail
+/// #[micro_cpuflags::cpu_dispatch(avx3, fallback)]
+/// fn sum(data: &[u8]) -> u32 { data.iter().map(|&x| x as u32).sum() }
+/// ```
+///
+/// The fallback is required:
+///
+/// ```compile_fail
+/// #[micro_cpuflags::cpu_dispatch(avx2, sse4.2)]
+/// fn sum(data: &[u8]) -> u32 { data.iter().map(|&x| x as u32).sum() }
+/// ```
+///
+/// And must be the last variant:
+///
+/// ```compile_fail
+/// #[micro_cpuflags::cpu_dispatch(avx2, fallback, sse4.2)]
+/// fn sum(data: &[u8]) -> u32 { data.iter().map(|&x| x as u32).sum() }
+/// ```
+#[cfg(feature = "macros")]
+pub use micro_cpuflags_macros::cpu_dispatch;
\ No newline at end of file
@@ -6,6 +6,18 @@
 // at https://mozilla.org/MPL/2.0/.
 
 
+pub use crate::cpuid::CPUID;
+
+pub use crate::dispatch::Dispatcher;
+
 pub use crate::info::CPUInfo;
 
-pub use crate::simd::SIMDFlags;
\ No newline at end of file
+pub use crate::isa::ISAFlags;
+
+pub use crate::level::X86Level;
+
+pub use crate::simd::SIMDFlags;
+
+pub use crate::target::Target;
+
+pub use crate::tier::{ Recommendation, SIMDTier };
\ No newline at end of file
@@ -8,18 +8,8 @@
 
 
 
-#[cfg(target_arch = "x86")]
-use core::arch::x86::{
-	__cpuid       as cpuid,
-	__cpuid_count as cpuidex,
-};
-
-#[cfg(target_arch = "x86_64")]
-use core::arch::x86_64::{
-	__cpuid       as cpuid,
-	__cpuid_count as cpuidex,
-	_xgetbv       as xgetbv,
-};
+use crate::cpuid::CPUID;
+use crate::error::Error;
 
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -126,6 +116,7 @@ impl SIMDFlags {
 	pub const AVX512BF16 : u32 = 1 << 24;
 
 	pub const AVX512VPCLMUL : u32 = 1 << 28;
+	pub const AVX512VAES    : u32 = 1 << 29;
 	// ****************************************************
 
 
@@ -137,35 +128,43 @@ impl SIMDFlags {
 		(self.simd & f) != 0
 	}
 
+	/// Returns `true` if the AVX-512 extension flag given is present.
+	#[inline(always)]
+	pub fn avx512(&self, f: u32) -> bool {
+		(self.avx512 & f) != 0
+	}
 
 
-	/// Reads the SIMD flags from CPUID.
-	pub fn read() -> SIMDFlags {
-		let (simd, avx512) = Self::inner();
 
-		SIMDFlags { simd, avx512 }
+	/// Returns the SIMD flags of the host, read once per process.
+	pub fn read() -> SIMDFlags {
+		crate::cache::get().simd()
 	}
 
+	/// Returns the SIMD flags of the host, or `Error::CpuidUnavailable`.
+	pub fn try_read() -> Result<SIMDFlags, Error> {
+		Ok( crate::cache::try_get()?.simd() )
+	}
 
-	/// Performs CPUID checks for SIMD features.
-	fn inner() -> (u32, u32) {
+	/// Decodes the SIMD flags from a CPUID dump.
+	pub fn from(cpuid: &CPUID) -> SIMDFlags {
+		let (simd, avx512) = Self::inner(cpuid);
 
-		// Get number of low and high leafs.
-		let lo = unsafe { cpuid(0x00000000).eax };
-		let hi = unsafe { cpuid(0x80000000).eax };
+		SIMDFlags { simd, avx512 }
+	}
 
 
+	/// Performs CPUID checks for SIMD features.
+	fn inner(cpuid: &CPUID) -> (u32, u32) {
 		let mut simd   = 0;
 		let mut avx512 = 0;
 
-		if lo < 0x1 { return (simd, avx512); }
-
 		// CPUID 0x00000001
-		{
-			let info = unsafe { cpuid(0x00000001) };
+		if let Some(info) = cpuid.leaf(0x00000001) {
 
 			if ( info.ecx        & 1) == 1 { simd |= Self::SSE3  }
 			if ((info.ecx >>  9) & 1) == 1 { simd |= Self::SSSE3 }
+			if ((info.ecx >> 12) & 1) == 1 { simd |= Self::FMA3  }
 			if ((info.ecx >> 19) & 1) == 1 { simd |= Self::SSE41 }
 			if ((info.ecx >> 20) & 1) == 1 { simd |= Self::SSE42 }
 
@@ -177,9 +176,7 @@ impl SIMDFlags {
 				0b111 => {
 					simd |= Self::OSXSAVE | Self::XSAVE;
 
-					let xcr = unsafe { xgetbv(0) };
-
-					match xcr & 0xE6 {
+					match cpuid.xcr0() & 0xE6 {
 						0xE6 => simd |= Self::AVX | Self::AVX512,
 						0x6  => simd |= Self::AVX,
 						_ => (),
@@ -195,13 +192,9 @@ impl SIMDFlags {
 			}
 		}
 
-		if lo < 0x7 { return (simd, avx512); }
-
 		// CPUID 0x00000007
-		{
-			let info = unsafe { cpuid(0x00000007) };
-
-			if ((info.ebx >> 3)  & 1) == 1 { simd |= Self::AVX2 }
+		if let Some(info) = cpuid.leaf(0x00000007) {
+			if ((info.ebx >> 5)  & 1) == 1 { simd |= Self::AVX2 }
 
 
 			if ((info.ebx >> 16) & 1) == 1 { avx512 |= Self::AVX512F    }
@@ -215,6 +208,9 @@ impl SIMDFlags {
 
 			if ((info.ecx >>  1) & 1) == 1 { avx512 |= Self::AVX512VBMI      }
 			if ((info.ecx >>  6) & 1) == 1 { avx512 |= Self::AVX512VBMI2     }
+			if ((info.ecx >>  8) & 1) == 1 { avx512 |= Self::AVX512GFNI      }
+			if ((info.ecx >>  9) & 1) == 1 { avx512 |= Self::AVX512VAES      }
+			if ((info.ecx >> 10) & 1) == 1 { avx512 |= Self::AVX512VPCLMUL   }
 			if ((info.ecx >> 11) & 1) == 1 { avx512 |= Self::AVX512VNNI      }
 			if ((info.ecx >> 12) & 1) == 1 { avx512 |= Self::AVX512BITALG    }
 			if ((info.ecx >> 14) & 1) == 1 { avx512 |= Self::AVX512VPOPCNTDQ }
@@ -224,16 +220,15 @@ impl SIMDFlags {
 			if ((info.edx >>  3) & 1) == 1 { avx512 |= Self::AVX5124FMAPS       }
 			if ((info.edx >>  8) & 1) == 1 { avx512 |= Self::AVX512VP2INTERSECT }
 
-			let info = unsafe { cpuidex(0x7, 1) };
+		}
 
+		// CPUID 0x00000007, sub-leaf 1
+		if let Some(info) = cpuid.subleaf(0x00000007, 1) {
 			if ((info.eax >> 5) & 1) == 1 { avx512 |= Self::AVX512BF16 }
 		}
 
-		if hi < 0x80000001 { return (simd, avx512); }
-
 		// CPUID 0x80000001
-		{
-			let info = unsafe { cpuid(0x80000001) };
+		if let Some(info) = cpuid.leaf(0x80000001) {
 
 			if ((info.ecx >>  6) & 1) == 1 { simd |= Self::SSE4A }
 			if ((info.ecx >> 11) & 1) == 1 { simd |= Self::XOP   }
@@ -246,38 +241,36 @@ impl SIMDFlags {
 	}
 }
 
-impl std::fmt::Display for SIMDFlags {
-	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-		let mut args = String::new();
-
-		args += "CPU SIMD features\n";
+impl core::fmt::Display for SIMDFlags {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		f.write_str("CPU SIMD features\n")?;
 
-		if self.simd(Self::MMX) { args += "  MMX\n\n"; }
+		if self.simd(Self::MMX) { f.write_str("  MMX\n\n")?; }
 
-		args += "SIMD 128-bit:\n";
+		f.write_str("SIMD 128-bit:\n")?;
 
-		if self.simd(Self::SSE1)  { args += "  SSE    (Streaming SIMD Extensions)\n"; }
-		if self.simd(Self::SSE2)  { args += "  SSE2   (Streaming SIMD Extensions) (Instruction Set   2)\n"; }
-		if self.simd(Self::SSE3)  { args += "  SSE3   (Streaming SIMD Extensions) (Instruction Set   3)\n"; }
-		if self.simd(Self::SSE41) { args += "  SSE4.1 (Streaming SIMD Extensions) (Instruction Set 4.1)\n"; }
-		if self.simd(Self::SSE42) { args += "  SSE4.2 (Streaming SIMD Extensions) (Instruction Set 4.2)\n"; }
-		if self.simd(Self::SSE4A) { args += "  SSE4.A (Streaming SIMD Extensions) (Instruction Set 4.A)\n"; }
-		if self.simd(Self::SSE5)  { args += "  SSE5   (Streaming SIMD Extensions) (Instruction Set   5)\n"; }
-		if self.simd(Self::SSSE3) { args += "  SSSE3  (Supplemental Streaming SIMD Extensions) (Instruction Set 3)\n\n"; }
+		if self.simd(Self::SSE1)  { f.write_str("  SSE    (Streaming SIMD Extensions)\n")?; }
+		if self.simd(Self::SSE2)  { f.write_str("  SSE2   (Streaming SIMD Extensions) (Instruction Set   2)\n")?; }
+		if self.simd(Self::SSE3)  { f.write_str("  SSE3   (Streaming SIMD Extensions) (Instruction Set   3)\n")?; }
+		if self.simd(Self::SSE41) { f.write_str("  SSE4.1 (Streaming SIMD Extensions) (Instruction Set 4.1)\n")?; }
+		if self.simd(Self::SSE42) { f.write_str("  SSE4.2 (Streaming SIMD Extensions) (Instruction Set 4.2)\n")?; }
+		if self.simd(Self::SSE4A) { f.write_str("  SSE4.A (Streaming SIMD Extensions) (Instruction Set 4.A)\n")?; }
+		if self.simd(Self::SSE5)  { f.write_str("  SSE5   (Streaming SIMD Extensions) (Instruction Set   5)\n")?; }
+		if self.simd(Self::SSSE3) { f.write_str("  SSSE3  (Supplemental Streaming SIMD Extensions) (Instruction Set 3)\n\n")?; }
 
-		if self.simd(Self::XOP) { args += "  XOP (eXtended Operations) (AMD®)\n\n"; }
+		if self.simd(Self::XOP) { f.write_str("  XOP (eXtended Operations) (AMD®)\n\n")?; }
 
-		args += "SIMD 256-bit:\n";
+		f.write_str("SIMD 256-bit:\n")?;
 
 
-		if self.simd(Self::AVX)    { args += "  AVX    (Advanced Vector Extensions)\n"; }
-		if self.simd(Self::AVX2)   { args += "  AVX2   (Advanced Vector Extensions) (Instruction Set 2)\n\n"; }
+		if self.simd(Self::AVX)    { f.write_str("  AVX    (Advanced Vector Extensions)\n")?; }
+		if self.simd(Self::AVX2)   { f.write_str("  AVX2   (Advanced Vector Extensions) (Instruction Set 2)\n\n")?; }
 
-		args += "SIMD 512-bit:\n";
+		f.write_str("SIMD 512-bit:\n")?;
 
-		if self.simd(Self::AVX512) { args += "  AVX512 (Advanced Vector Extensions)\n\n"; }
+		if self.simd(Self::AVX512) { f.write_str("  AVX512 (Advanced Vector Extensions)\n\n")?; }
 
 
-		f.write_str(&args)
+		Ok(())
 	}
 }
@@ -0,0 +1,53 @@
+//! .NET Native AOT instruction set.
+
+
+// This Source Code Form is subject to the terms of the
+// Mozilla Public License, v. 2.0. If a copy of the MPL
+// was not distributed with this file, You can obtain one
+// at https://mozilla.org/MPL/2.0/.
+
+
+
+use crate::isa::ISAFlags;
+use crate::level::X86Level;
+
+use super::Target;
+
+
+
+/// `IlcInstructionSet` property of a target.
+/// Follows the features of the CPU, not its model.
+#[derive(Debug, Clone, Copy)]
+pub struct DotNetFlags {
+	target: Target,
+}
+
+
+impl DotNetFlags {
+	/// Creates the property of a target.
+	pub fn from(target: Target) -> Self {
+		DotNetFlags { target }
+	}
+
+	/// Returns the instruction set, `None` if the CPU is not 64-bit.
+	pub fn instruction_set(&self) -> Option<&'static str> {
+		if !self.target.isa().isa(ISAFlags::LM) { return None; }
+
+		self.target.level().level().map(|level| match level {
+			X86Level::V1 => "x86-x64",
+			X86Level::V2 => "x86-x64-v2",
+			X86Level::V3 => "x86-x64-v3",
+			X86Level::V4 => "x86-x64-v4",
+		})
+	}
+}
+
+
+impl core::fmt::Display for DotNetFlags {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		match self.instruction_set() {
+			Some(set) => write!(f, "-p:IlcInstructionSet={}", set),
+			_ => Ok(()),
+		}
+	}
+}
@@ -0,0 +1,80 @@
+//! GCC and Clang code generation flags.
+
+
+// This Source Code Form is subject to the terms of the
+// Mozilla Public License, v. 2.0. If a copy of the MPL
+// was not distributed with this file, You can obtain one
+// at https://mozilla.org/MPL/2.0/.
+
+
+
+use crate::feature::Feature;
+
+use super::Target;
+
+
+
+/// LLVM CPU names spelled differently by GCC.
+const RENAMES: [(&str, &str); 1] = [
+	("yonah", "prescott"),
+];
+
+
+
+/// `-march`, `-mtune` and `-m` flags of a target.
+///
+/// Every feature of the table with a `-m` option is listed, enabled or
+/// disabled, so the flags stay correct when the CPU lacks a feature of its
+/// model (e.g. a virtual machine hiding AVX-512).
+#[derive(Debug, Clone, Copy)]
+pub struct GCCFlags {
+	target: Target,
+}
+
+
+impl GCCFlags {
+	/// Creates the flags of a target.
+	pub fn from(target: Target) -> Self {
+		GCCFlags { target }
+	}
+
+	/// Returns the `-march` name.
+	/// Falls back to the x86-64 psABI level for unknown 64-bit models.
+	pub fn arch(&self) -> Option<&'static str> {
+		self.target.cpu().map(|cpu| match RENAMES.iter().find(|(llvm, _)| *llvm == cpu) {
+			Some((_, gcc)) => *gcc,
+			_ => cpu,
+		})
+	}
+
+	/// Returns the `-mtune` name, `generic` for unknown models.
+	pub fn tune(&self) -> &'static str {
+		match self.target.model().model().llvm() {
+			Some(_) => self.arch().unwrap_or("generic"),
+			_ => "generic",
+		}
+	}
+
+	/// Returns the `-m` option names of the features with their state.
+	pub fn features(&self) -> impl Iterator<Item = (&'static str, bool)> + '_ {
+		Feature::all().iter()
+			.filter_map(move |f| f.gcc().map(|name| (name, self.target.has(f))))
+	}
+}
+
+
+impl core::fmt::Display for GCCFlags {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		if let Some(arch) = self.arch() {
+			write!(f, "-march={} ", arch)?;
+		}
+
+		write!(f, "-mtune={}", self.tune())?;
+
+		for (name, enabled) in self.features() {
+			write!(f, " -m{}{}", if enabled { "" } else { "no-" }, name)?;
+		}
+
+		Ok(())
+	}
+}
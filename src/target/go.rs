@@ -0,0 +1,62 @@
+//! Go build environment.
+
+
+// This Source Code Form is subject to the terms of the
+// Mozilla Public License, v. 2.0. If a copy of the MPL
+// was not distributed with this file, You can obtain one
+// at https://mozilla.org/MPL/2.0/.
+
+
+
+use crate::isa::ISAFlags;
+use crate::level::X86Level;
+use crate::simd::SIMDFlags;
+
+use super::Target;
+
+
+
+/// `GOAMD64` and `GO386` variables of a target.
+/// Both follow the features of the CPU, not its model.
+#[derive(Debug, Clone, Copy)]
+pub struct GoEnv {
+	target: Target,
+}
+
+
+impl GoEnv {
+	/// Creates the environment of a target.
+	pub fn from(target: Target) -> Self {
+		GoEnv { target }
+	}
+
+	/// Returns the `GOAMD64` value, `None` if the CPU is not 64-bit.
+	pub fn goamd64(&self) -> Option<&'static str> {
+		if !self.target.isa().isa(ISAFlags::LM) { return None; }
+
+		self.target.level().level().map(|level| match level {
+			X86Level::V1 => "v1",
+			X86Level::V2 => "v2",
+			X86Level::V3 => "v3",
+			X86Level::V4 => "v4",
+		})
+	}
+
+	/// Returns the `GO386` value.
+	pub fn go386(&self) -> &'static str {
+		match self.target.simd().simd(SIMDFlags::SSE2) {
+			true => "sse2",
+			_ => "softfloat",
+		}
+	}
+}
+
+
+impl core::fmt::Display for GoEnv {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		match self.goamd64() {
+			Some(level) => write!(f, "GOAMD64={}", level),
+			_ => write!(f, "GO386={}", self.go386()),
+		}
+	}
+}
@@ -0,0 +1,89 @@
+//! HotSpot JVM options.
+
+
+// This Source Code Form is subject to the terms of the
+// Mozilla Public License, v. 2.0. If a copy of the MPL
+// was not distributed with this file, You can obtain one
+// at https://mozilla.org/MPL/2.0/.
+
+
+
+use crate::feature::Feature;
+use crate::simd::SIMDFlags;
+
+use super::Target;
+
+
+
+/// Boolean HotSpot options and the feature they need.
+const OPTIONS: [(&str, &str); 8] = [
+	("UsePopCountInstruction",          "popcnt"),
+	("UseCountLeadingZerosInstruction", "lzcnt"),
+	("UseBMI1Instructions",             "bmi1"),
+	("UseBMI2Instructions",             "bmi2"),
+	("UseFMA",                          "fma"),
+	("UseAES",                          "aes"),
+	("UseCLMUL",                        "pclmulqdq"),
+	("UseSHA",                          "sha"),
+];
+
+
+
+/// `-XX:UseSSE`, `-XX:UseAVX` and instruction options of a target.
+/// All follow the features of the CPU, not its model.
+#[derive(Debug, Clone, Copy)]
+pub struct JVMFlags {
+	target: Target,
+}
+
+
+impl JVMFlags {
+	/// Creates the options of a target.
+	pub fn from(target: Target) -> Self {
+		JVMFlags { target }
+	}
+
+	/// Returns the `UseSSE` level: 0 to 4 (SSE4.1).
+	pub fn use_sse(&self) -> u8 {
+		let simd = self.target.simd();
+
+		match () {
+			_ if simd.simd(SIMDFlags::SSE41) => 4,
+			_ if simd.simd(SIMDFlags::SSE3)  => 3,
+			_ if simd.simd(SIMDFlags::SSE2)  => 2,
+			_ if simd.simd(SIMDFlags::SSE1)  => 1,
+			_ => 0,
+		}
+	}
+
+	/// Returns the `UseAVX` level: 0 to 3 (AVX-512).
+	pub fn use_avx(&self) -> u8 {
+		let has = |name| Feature::find(name).is_some_and(|f| self.target.has(f));
+
+		match () {
+			_ if has("avx512f") => 3,
+			_ if has("avx2")    => 2,
+			_ if has("avx")     => 1,
+			_ => 0,
+		}
+	}
+
+	/// Returns the boolean options with their state.
+	pub fn options(&self) -> impl Iterator<Item = (&'static str, bool)> + '_ {
+		OPTIONS.iter()
+			.map(move |(option, name)| (*option, Feature::find(name).is_some_and(|f| self.target.has(f))))
+	}
+}
+
+
+impl core::fmt::Display for JVMFlags {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		write!(f, "-XX:UseSSE={} -XX:UseAVX={}", self.use_sse(), self.use_avx())?;
+
+		for (option, enabled) in self.options() {
+			write!(f, " -XX:{}{}", if enabled { '+' } else { '-' }, option)?;
+		}
+
+		Ok(())
+	}
+}
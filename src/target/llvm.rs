@@ -0,0 +1,232 @@
+//! LLVM host CPU name.
+
+
+// This Source Code Form is subject to the terms of the
+// Mozilla Public License, v. 2.0. If a copy of the MPL
+// was not distributed with this file, You can obtain one
+// at https://mozilla.org/MPL/2.0/.
+
+
+
+use crate::feature::Feature;
+use crate::info::{ CPUModel, CPUVendor };
+use crate::isa::ISAFlags;
+
+use super::Target;
+
+
+
+/// Host CPU name of a target, as returned by LLVM 19 `sys::getHostCPUName`.
+///
+/// The name follows LLVM and not the model database: LLVM groups some models
+/// (e.g. Raptor Lake and Meteor Lake resolve to `alderlake`), tells the
+/// Skylake-SP family apart by its features and guesses unknown models from
+/// their features. Vendors other than Intel and AMD resolve to `generic`.
+#[derive(Debug, Clone, Copy)]
+pub struct LLVMHost {
+	target: Target,
+}
+
+
+impl LLVMHost {
+	/// Creates the host CPU name of a target.
+	pub fn from(target: Target) -> Self {
+		LLVMHost { target }
+	}
+
+	/// Returns the LLVM CPU name.
+	pub fn name(&self) -> &'static str {
+		let name = match self.target.model().vendor() {
+			CPUVendor::Intel => self.intel(),
+			CPUVendor::AMD   => self.amd(),
+			_ => None,
+		};
+
+		name.unwrap_or("generic")
+	}
+
+
+	/// Resolves Intel CPUs.
+	fn intel(&self) -> Option<&'static str> {
+		use CPUModel::*;
+
+		let model = self.target.model();
+		let cpu   = CPUModel::from(model.vendor(), model.signature());
+
+		let name = match model.family() {
+			0x03 => "i386",
+			0x04 => "i486",
+
+			0x05 if self.has("mmx") => "pentium-mmx",
+			0x05 => "pentium",
+
+			0x06 => match cpu {
+				Conroe(_) => "core2",
+				Penryn(_) => "penryn",
+
+				Nehalem(0x25 | 0x2C | 0x2F) => "westmere",
+				Nehalem(_) => "nehalem",
+
+				SandyBridge(_) => "sandybridge",
+				IvyBridge(_)   => "ivybridge",
+				Haswell(_)     => "haswell",
+				Broadwell(_)   => "broadwell",
+
+				// LLVM ignores the stepping of 06_55H.
+				SkyLake(0x55) | CascadeLake(_) | CooperLake(_) => match () {
+					_ if self.has("avx512bf16") => "cooperlake",
+					_ if self.has("avx512vnni") => "cascadelake",
+					_ => "skylake-avx512",
+				},
+
+				SkyLake(_) | KabyLake(_) | KabyLakeR(_) | AmberLake(_) | WhiskeyLake(_) | CoffeeLake(_) | CometLake(_) => "skylake",
+
+				PalmCove(_) => "cannonlake",
+
+				SunnyCove(0x6A | 0x6C) => "icelake-server",
+				SunnyCove(0x7D | 0x7E) => "icelake-client",
+				SunnyCove(0x8A) | Tremont(_) => "tremont",
+
+				WillowCove(_)  => "tigerlake",
+				CypressCove(_) => "rocketlake",
+
+				GoldenCove(0x8F) | RaptorCove(0xCF) => "sapphirerapids",
+				GoldenCove(_) | RaptorCove(_) | Gracemont(_) | RedwoodCove(0xAA | 0xAC) => "alderlake",
+
+				RedwoodCove(0xAD) => "graniterapids",
+				RedwoodCove(0xAE) => "graniterapids-d",
+
+				RedwoodCove(0xB5) | LionCove(0xC5) => "arrowlake",
+				LionCove(0xBD | 0xC6) => "arrowlake-s",
+
+				Bonnel(_) | Saltwell(_)     => "bonnell",
+				Silvermont(_) | Airmont(_)  => "silvermont",
+				Goldmont(_)                 => "goldmont",
+				GoldmontPlus(_)             => "goldmont-plus",
+
+				Crestmont(0xAF) => "sierraforest",
+				Crestmont(0xB6) => "grandridge",
+
+				KnightsLanding(_) => "knl",
+				KnightsMill(_)    => "knm",
+
+				// Models known to LLVM but not to the database.
+				_ => match model.model_number() {
+					0xCC => "pantherlake",
+					0xDD => "clearwaterforest",
+					_ => self.guess(),
+				},
+			},
+
+			0x0F if self.target.isa().isa(ISAFlags::LM) => "nocona",
+			0x0F if self.has("sse3") => "prescott",
+			0x0F => "pentium4",
+
+			_ => return None,
+		};
+
+		Some(name)
+	}
+
+	/// Guesses unknown Intel family 6 models from their features.
+	fn guess(&self) -> &'static str {
+		match () {
+			_ if self.has("avx512vp2intersect") => "tigerlake",
+			_ if self.has("avx512vbmi2") => "icelake-client",
+			_ if self.has("avx512vbmi")  => "cannonlake",
+			_ if self.has("avx512bf16")  => "cooperlake",
+			_ if self.has("avx512vnni")  => "cascadelake",
+			_ if self.has("avx512vl")    => "skylake-avx512",
+
+			_ if self.has("clflushopt") && self.has("sha") => "goldmont",
+			_ if self.has("clflushopt") => "skylake",
+
+			_ if self.has("adx")  => "broadwell",
+			_ if self.has("avx2") => "haswell",
+			_ if self.has("avx")  => "sandybridge",
+
+			_ if self.has("sse4.2") && self.has("movbe") => "silvermont",
+			_ if self.has("sse4.2") => "nehalem",
+			_ if self.has("sse4.1") => "penryn",
+
+			_ if self.has("ssse3") && self.has("movbe") => "bonnell",
+			_ if self.has("ssse3") => "core2",
+
+			_ if self.target.isa().isa(ISAFlags::LM) => "core2",
+
+			_ if self.has("sse3") => "yonah",
+			_ if self.has("sse2") => "pentium-m",
+			_ if self.has("sse")  => "pentium3",
+			_ if self.has("mmx")  => "pentium2",
+
+			_ => "pentiumpro",
+		}
+	}
+
+	/// Resolves AMD CPUs.
+	fn amd(&self) -> Option<&'static str> {
+		use CPUModel::*;
+
+		let model = self.target.model();
+		let cpu   = CPUModel::from(model.vendor(), model.signature());
+
+		let name = match model.family() {
+			0x04 => "i486",
+
+			0x05 => match cpu {
+				K6(0x06 | 0x07) => "k6",
+				K6(0x08) => "k6-2",
+				K6(_) => "k6-3",
+				Geode(_) => "geode",
+				_ => "pentium",
+			},
+
+			0x06 if self.has("sse") => "athlon-xp",
+			0x06 => "athlon",
+
+			0x0F if self.has("sse3") => "k8-sse3",
+			0x0F => "k8",
+
+			0x10 => "amdfam10",
+			0x14 => "btver1",
+			0x16 => "btver2",
+
+			0x15 => match cpu {
+				Piledriver(_)  => "bdver2",
+				Steamroller(_) => "bdver3",
+				Excavator(_)   => "bdver4",
+				_ => "bdver1",
+			},
+
+			// LLVM matches whole model ranges, wider than the database.
+			0x17 => match cpu {
+				Zen2(_) => "znver2",
+				Unknown(_) if matches!(model.model_number(), 0x30..=0x3F | 0x47 | 0x60..=0x7F | 0x84..=0x87 | 0x90..=0xAF) => "znver2",
+				_ => "znver1",
+			},
+
+			0x19 => match cpu {
+				Zen4(_) | Zen4c(_) => "znver4",
+				_ => "znver3",
+			},
+
+			0x1A => "znver5",
+
+			_ => return None,
+		};
+
+		Some(name)
+	}
+
+	/// Returns `true` if the feature with the given LLVM name is usable.
+	fn has(&self, name: &str) -> bool {
+		Feature::find(name).is_some_and(|f| self.target.has(f))
+	}
+}
+
+
+impl core::fmt::Display for LLVMHost {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		f.write_str(self.name())
+	}
+}
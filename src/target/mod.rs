@@ -0,0 +1,128 @@
+//! Code generation targets.
+//! Translates the detected features and model into compiler flags.
+
+
+// This Source Code Form is subject to the terms of the
+// Mozilla Public License, v. 2.0. If a copy of the MPL
+// was not distributed with this file, You can obtain one
+// at https://mozilla.org/MPL/2.0/.
+
+
+
+mod dotnet;
+mod gcc;
+mod go;
+mod jvm;
+mod llvm;
+mod rustc;
+
+
+pub use self::{
+	dotnet::DotNetFlags,
+	gcc::GCCFlags,
+	go::GoEnv,
+	jvm::JVMFlags,
+	llvm::LLVMHost,
+	rustc::RustcFlags,
+};
+
+
+use crate::cpuid::CPUID;
+use crate::feature::Feature;
+use crate::info::Model;
+use crate::isa::ISAFlags;
+use crate::level::{ LevelReport, X86Level };
+use crate::simd::SIMDFlags;
+
+
+
+/// Model and features of a CPU, the input of the exporters.
+#[derive(Debug, Clone, Copy)]
+pub struct Target {
+	/// Model of the CPU.
+	model: Model,
+
+	/// ISA flags.
+	isa: ISAFlags,
+
+	/// SIMD flags.
+	simd: SIMDFlags,
+}
+
+
+impl Target {
+	/// Returns the target of the host, read once per process.
+	pub fn read() -> Self {
+		crate::cache::get().target()
+	}
+
+	/// Decodes the target from a CPUID dump.
+	pub fn from(cpuid: &CPUID) -> Self {
+		Target { model: Model::from(cpuid), isa: ISAFlags::from(cpuid), simd: SIMDFlags::from(cpuid) }
+	}
+
+	/// Returns the model.
+	pub fn model(&self) -> Model {
+		self.model
+	}
+
+	/// Returns the ISA flags.
+	pub fn isa(&self) -> ISAFlags {
+		self.isa
+	}
+
+	/// Returns the SIMD flags.
+	pub fn simd(&self) -> SIMDFlags {
+		self.simd
+	}
+
+	/// Returns `true` if the feature is present and usable.
+	pub fn has(&self, feature: &Feature) -> bool {
+		feature.detected(&self.isa, &self.simd)
+	}
+
+	/// Returns the x86-64 psABI level met.
+	pub fn level(&self) -> LevelReport {
+		X86Level::check(&self.isa, &self.simd)
+	}
+
+	/// Returns the rustc flags of the target.
+	pub fn rustc(&self) -> RustcFlags {
+		RustcFlags::from(*self)
+	}
+
+	/// Returns the LLVM host CPU name of the target.
+	pub fn llvm(&self) -> LLVMHost {
+		LLVMHost::from(*self)
+	}
+
+	/// Returns the GCC and Clang flags of the target.
+	pub fn gcc(&self) -> GCCFlags {
+		GCCFlags::from(*self)
+	}
+
+	/// Returns the Go environment of the target.
+	pub fn go(&self) -> GoEnv {
+		GoEnv::from(*self)
+	}
+
+	/// Returns the HotSpot JVM options of the target.
+	pub fn jvm(&self) -> JVMFlags {
+		JVMFlags::from(*self)
+	}
+
+	/// Returns the .NET Native AOT property of the target.
+	pub fn dotnet(&self) -> DotNetFlags {
+		DotNetFlags::from(*self)
+	}
+
+	/// Returns the LLVM CPU name of the model.
+	/// Falls back to the x86-64 psABI level for unknown 64-bit models.
+	fn cpu(&self) -> Option<&'static str> {
+		match self.model.model().llvm() {
+			Some(name) => Some(name),
+			_ if self.isa.isa(ISAFlags::LM) => self.level().level().map(|level| level.name()),
+			_ => None,
+		}
+	}
+}
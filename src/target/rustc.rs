@@ -0,0 +1,67 @@
+//! rustc code generation flags.
+
+
+// This Source Code Form is subject to the terms of the
+// Mozilla Public License, v. 2.0. If a copy of the MPL
+// was not distributed with this file, You can obtain one
+// at https://mozilla.org/MPL/2.0/.
+
+
+
+use crate::feature::Feature;
+
+use super::Target;
+
+
+
+/// `-C target-cpu` and `-C target-feature` flags of a target.
+///
+/// Every feature of the table known to rustc is listed, enabled or disabled,
+/// so the flags stay correct when the CPU lacks a feature of its model
+/// (e.g. a virtual machine hiding AVX-512).
+#[derive(Debug, Clone, Copy)]
+pub struct RustcFlags {
+	target: Target,
+}
+
+
+impl RustcFlags {
+	/// Creates the flags of a target.
+	pub fn from(target: Target) -> Self {
+		RustcFlags { target }
+	}
+
+	/// Returns the LLVM CPU name.
+	/// Falls back to the x86-64 psABI level for unknown 64-bit models.
+	pub fn cpu(&self) -> Option<&'static str> {
+		self.target.cpu()
+	}
+
+	/// Returns the rustc names of the features with their state.
+	pub fn features(&self) -> impl Iterator<Item = (&'static str, bool)> + '_ {
+		Feature::all().iter()
+			.filter_map(move |f| f.rustc().map(|name| (name, self.target.has(f))))
+	}
+}
+
+
+impl core::fmt::Display for RustcFlags {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		if let Some(cpu) = self.cpu() {
+			write!(f, "-C target-cpu={} ", cpu)?;
+		}
+
+		f.write_str("-C target-feature=")?;
+
+		for (i, (name, enabled)) in self.features().enumerate() {
+			let sep = match i {
+				0 => "",
+				_ => ",",
+			};
+
+			write!(f, "{}{}{}", sep, if enabled { '+' } else { '-' }, name)?;
+		}
+
+		Ok(())
+	}
+}
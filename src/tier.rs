@@ -0,0 +1,396 @@
+//! SIMD tier and vector width recommendation.
+//! Combines the usable SIMD flags with the model of the CPU.
+
+
+// This Source Code Form is subject to the terms of the
+// Mozilla Public License, v. 2.0. If a copy of the MPL
+// was not distributed with this file, You can obtain one
+// at https://mozilla.org/MPL/2.0/.
+
+
+
+use crate::cpuid::CPUID;
+use crate::info::{ Caveat, CPUVendor, Model };
+use crate::simd::SIMDFlags;
+
+
+
+/// Returns the recommended SIMD tier of the host.
+pub fn recommended_tier() -> SIMDTier {
+	Recommendation::read().tier()
+}
+
+/// Returns the preferred vector width of the host in bits.
+pub fn preferred_vector_width() -> u16 {
+	Recommendation::read().width()
+}
+
+
+
+/// Tier of SIMD code, ordered from the narrowest to the widest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SIMDTier {
+	/// No vector extension.
+	Scalar,
+
+	/// SSE and SSE2.
+	SSE2,
+
+	/// SSE3, SSSE3, SSE4.1 and SSE4.2.
+	SSE42,
+
+	/// AVX.
+	AVX,
+
+	/// AVX2.
+	AVX2,
+
+	/// AVX-512 F, CD, BW, DQ and VL.
+	AVX512,
+}
+
+
+impl SIMDTier {
+	/// Returns the widest tier usable with the given flags.
+	pub fn from(simd: &SIMDFlags) -> SIMDTier {
+		let avx512 = [SIMDFlags::AVX512F, SIMDFlags::AVX512CD, SIMDFlags::AVX512BW, SIMDFlags::AVX512DQ, SIMDFlags::AVX512VL]
+			.iter()
+			.all(|f| simd.avx512(*f));
+
+		let sse42 = simd.simd(SIMDFlags::SSE3) && simd.simd(SIMDFlags::SSSE3) && simd.simd(SIMDFlags::SSE41) && simd.simd(SIMDFlags::SSE42);
+
+		match () {
+			_ if simd.simd(SIMDFlags::AVX512) && avx512 => SIMDTier::AVX512,
+			_ if simd.simd(SIMDFlags::AVX) && simd.simd(SIMDFlags::AVX2) => SIMDTier::AVX2,
+			_ if simd.simd(SIMDFlags::AVX) => SIMDTier::AVX,
+			_ if sse42 && simd.simd(SIMDFlags::SSE2) => SIMDTier::SSE42,
+			_ if simd.simd(SIMDFlags::SSE1) && simd.simd(SIMDFlags::SSE2) => SIMDTier::SSE2,
+			_ => SIMDTier::Scalar,
+		}
+	}
+
+	/// Returns the maximum vector width of the tier in bits.
+	pub fn width(&self) -> u16 {
+		match *self {
+			SIMDTier::Scalar => 0,
+			SIMDTier::SSE2 | SIMDTier::SSE42 => 128,
+			SIMDTier::AVX | SIMDTier::AVX2 => 256,
+			SIMDTier::AVX512 => 512,
+		}
+	}
+}
+
+
+impl core::fmt::Display for SIMDTier {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		let args = match *self {
+			SIMDTier::Scalar => "Scalar",
+			SIMDTier::SSE2   => "SSE2",
+			SIMDTier::SSE42  => "SSE4.2",
+			SIMDTier::AVX    => "AVX",
+			SIMDTier::AVX2   => "AVX2",
+			SIMDTier::AVX512 => "AVX-512",
+		};
+
+		f.write_str(args)
+	}
+}
+
+
+
+/// AMD performance optimization hints. CPUID leaf 0x8000001A EAX.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PerfHints {
+	/// Hint flags. `None` if the leaf is not available.
+	hints: Option<u32>,
+}
+
+
+impl PerfHints {
+	/// 128-bit SSE instructions are executed at full width.
+	pub const FP128 : u32 = 1 << 0;
+
+	/// MOVU instructions are more efficient than MOVL/MOVH pairs.
+	pub const MOVU  : u32 = 1 << 1;
+
+	/// 256-bit AVX instructions are executed at full width.
+	pub const FP256 : u32 = 1 << 2;
+
+
+	/// Creates the hints from the raw EAX value.
+	pub const fn new(eax: u32) -> Self {
+		PerfHints { hints: Some(eax) }
+	}
+
+	/// Creates empty hints, when the leaf is not available.
+	pub const fn empty() -> Self {
+		PerfHints { hints: None }
+	}
+
+	/// Returns `Some(true)` if the hint given is present, `None` if the leaf is not available.
+	pub fn hint(&self, f: u32) -> Option<bool> {
+		self.hints.map(|h| (h & f) != 0)
+	}
+
+	/// Reads the hints from the cached CPUID dump. Only AMD and Hygon define the leaf.
+	pub fn read(vendor: CPUVendor) -> Self {
+		Self::from(vendor, crate::cache::get().cpuid())
+	}
+
+	/// Decodes the hints from a CPUID dump. Only AMD and Hygon define the leaf.
+	pub fn from(vendor: CPUVendor, cpuid: &CPUID) -> Self {
+		match vendor {
+			CPUVendor::AMD | CPUVendor::Hygon => (),
+			_ => return PerfHints::empty(),
+		}
+
+		match cpuid.leaf(0x8000001A) {
+			Some(info) => PerfHints::new(info.eax),
+			_ => PerfHints::empty(),
+		}
+	}
+}
+
+
+
+/// Reason of a recommendation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Reason {
+	/// The widest usable tier runs at its full width.
+	Native,
+
+	/// No vector extension is usable.
+	NoVector,
+
+	/// Hybrid part, AVX-512 is not available on all cores.
+	Hybrid,
+
+	/// Heavy 512-bit code lowers the core frequency.
+	AVX512Throttle,
+
+	/// Only one 512-bit FMA unit, 512-bit code has the throughput of 256-bit code.
+	SingleFMA512,
+
+	/// 512-bit operations are split into two 256-bit halves.
+	SplitFP512,
+
+	/// 256-bit operations are split into two 128-bit halves.
+	SplitFP256,
+
+	/// 128-bit operations are split into two 64-bit halves.
+	SplitFP128,
+}
+
+
+impl core::fmt::Display for Reason {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		let args = match *self {
+			Reason::Native         => "The widest usable extension runs at full width",
+			Reason::NoVector       => "No usable vector extension",
+			Reason::Hybrid         => "Hybrid CPU, AVX-512 is not available on all cores",
+			Reason::AVX512Throttle => "512-bit code lowers the core frequency, 256-bit AVX-512 code is preferred",
+			Reason::SingleFMA512   => "Single 512-bit FMA unit, 256-bit AVX-512 code has the same throughput",
+			Reason::SplitFP512     => "512-bit operations are split in two 256-bit halves, 256-bit AVX-512 code is preferred",
+			Reason::SplitFP256     => "256-bit operations are split in two 128-bit halves, 128-bit code is preferred",
+			Reason::SplitFP128     => "128-bit operations are split in two 64-bit halves, 64-bit code has the same throughput",
+		};
+
+		f.write_str(args)
+	}
+}
+
+
+
+/// SIMD tier and vector width recommended for a CPU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Recommendation {
+	/// Recommended SIMD tier.
+	tier: SIMDTier,
+
+	/// Preferred vector width in bits.
+	width: u16,
+
+	/// Reason of the recommendation.
+	reason: Reason,
+}
+
+
+impl Recommendation {
+	/// Reads the recommendation for the host from the cached CPUID dump.
+	pub fn read() -> Self {
+		Self::from(crate::cache::get().cpuid())
+	}
+
+	/// Decides the recommendation from a CPUID dump.
+	pub fn from(cpuid: &CPUID) -> Self {
+		let model = Model::from(cpuid);
+
+		Self::decide(&SIMDFlags::from(cpuid), &model, PerfHints::from(model.vendor(), cpuid), Self::hybrid(cpuid))
+	}
+
+	/// Decides the recommendation from the usable SIMD flags, the model,
+	/// the AMD performance hints and the hybrid status of the CPU.
+	pub fn decide(simd: &SIMDFlags, model: &Model, hints: PerfHints, hybrid: bool) -> Self {
+		let tier = SIMDTier::from(simd);
+
+		let profile = model.model().profile();
+
+		let caveat = |c| profile.is_some_and(|p| p.caveat(c));
+
+		let (tier, width, reason) = match tier {
+			SIMDTier::Scalar => (tier, 0, Reason::NoVector),
+
+			SIMDTier::AVX512 if hybrid => (SIMDTier::AVX2, 256, Reason::Hybrid),
+			SIMDTier::AVX512 if caveat(Caveat::AVX512Throttle) => (tier, 256, Reason::AVX512Throttle),
+			SIMDTier::AVX512 if caveat(Caveat::SplitFP512) => (tier, 256, Reason::SplitFP512),
+			SIMDTier::AVX512 if profile.is_some_and(|p| (p.width() == 512) && (p.fma() == 1)) => (tier, 256, Reason::SingleFMA512),
+
+			SIMDTier::AVX | SIMDTier::AVX2 if caveat(Caveat::SplitFP256) || (hints.hint(PerfHints::FP256) == Some(false)) => (tier, 128, Reason::SplitFP256),
+
+			SIMDTier::SSE2 | SIMDTier::SSE42 if caveat(Caveat::SplitFP128) || (hints.hint(PerfHints::FP128) == Some(false)) => (tier, 64, Reason::SplitFP128),
+
+			_ => (tier, tier.width(), Reason::Native),
+		};
+
+		Recommendation { tier, width, reason }
+	}
+
+	/// Returns the recommended SIMD tier.
+	pub fn tier(&self) -> SIMDTier {
+		self.tier
+	}
+
+	/// Returns the preferred vector width in bits.
+	pub fn width(&self) -> u16 {
+		self.width
+	}
+
+	/// Returns the reason of the recommendation.
+	pub fn reason(&self) -> Reason {
+		self.reason
+	}
+
+	/// Reads the hybrid flag. CPUID leaf 7 EDX bit 15.
+	fn hybrid(cpuid: &CPUID) -> bool {
+		cpuid.leaf(0x00000007).is_some_and(|info| ((info.edx >> 15) & 1) == 1)
+	}
+}
+
+
+impl core::fmt::Display for Recommendation {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		write!(f, "{} ({}-bit): {}", self.tier, self.width, self.reason)
+	}
+}
+
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use crate::cpuid::Registers;
+
+	const INTEL: (u32, u32, u32) = (0x756E6547, 0x6C65746E, 0x49656E69);
+	const AMD:   (u32, u32, u32) = (0x68747541, 0x444D4163, 0x69746E65);
+
+	/// Builds a dump with the given vendor, signature and widest enabled SIMD tier.
+	fn dump(vendor: (u32, u32, u32), eax: u32, tier: SIMDTier, hybrid: bool) -> CPUID {
+		let (ebx, ecx, edx) = vendor;
+
+		// SSE3, SSSE3, SSE4.1, SSE4.2 / SSE, SSE2.
+		let (mut ecx1, edx1) = match tier {
+			SIMDTier::Scalar => (0, 0),
+			SIMDTier::SSE2 => (0, (1 << 25) | (1 << 26)),
+			_ => ((1 << 0) | (1 << 9) | (1 << 19) | (1 << 20), (1 << 25) | (1 << 26)),
+		};
+
+		// AVX2 / AVX-512 F, DQ, CD, BW, VL.
+		let mut ebx7 = 0;
+
+		let xcr0 = match tier {
+			SIMDTier::AVX512 => 0xE7,
+			SIMDTier::AVX | SIMDTier::AVX2 => 0x07,
+			_ => 0x03,
+		};
+
+		if tier >= SIMDTier::AVX { ecx1 |= 0b111 << 26; }
+		if tier >= SIMDTier::AVX2 { ebx7 |= 1 << 5; }
+		if tier >= SIMDTier::AVX512 { ebx7 |= (1 << 16) | (1 << 17) | (1 << 28) | (1 << 30) | (1 << 31); }
+
+		let mut cpuid = CPUID::empty();
+
+		cpuid.insert(0x00, 0, Registers { eax: 0x07, ebx, ecx, edx });
+		cpuid.insert(0x01, 0, Registers { eax, ebx: 0, ecx: ecx1, edx: edx1 });
+		cpuid.insert(0x07, 0, Registers { eax: 0, ebx: ebx7, ecx: 0, edx: (hybrid as u32) << 15 });
+		cpuid.set_xcr0(xcr0);
+
+		cpuid
+	}
+
+	fn decide(cpuid: &CPUID, hints: PerfHints) -> (SIMDTier, u16, Reason) {
+		let r = Recommendation::decide(&SIMDFlags::from(cpuid), &Model::from(cpuid), hints, Recommendation::hybrid(cpuid));
+
+		(r.tier(), r.width(), r.reason())
+	}
+
+	#[test]
+	fn hybrid() {
+		// Alder Lake with AVX-512 enabled on the P-cores only.
+		let cpuid = dump(INTEL, 0x00090672, SIMDTier::AVX512, true);
+
+		assert_eq!(decide(&cpuid, PerfHints::empty()), (SIMDTier::AVX2, 256, Reason::Hybrid));
+		assert_eq!(Recommendation::from(&cpuid).reason(), Reason::Hybrid);
+	}
+
+	#[test]
+	fn avx512() {
+		// Skylake-SP.
+		let skylake = dump(INTEL, 0x00050654, SIMDTier::AVX512, false);
+		assert_eq!(decide(&skylake, PerfHints::empty()), (SIMDTier::AVX512, 256, Reason::AVX512Throttle));
+
+		// Zen 4.
+		let zen4 = dump(AMD, 0x00A10F11, SIMDTier::AVX512, false);
+		assert_eq!(decide(&zen4, PerfHints::new(0x07)), (SIMDTier::AVX512, 256, Reason::SplitFP512));
+
+		// Ice Lake client.
+		let icelake = dump(INTEL, 0x000706E5, SIMDTier::AVX512, false);
+		assert_eq!(decide(&icelake, PerfHints::empty()), (SIMDTier::AVX512, 256, Reason::SingleFMA512));
+
+		// Unknown model: the full width.
+		let unknown = dump(INTEL, 0x000F06F0, SIMDTier::AVX512, false);
+		assert_eq!(decide(&unknown, PerfHints::empty()), (SIMDTier::AVX512, 512, Reason::Native));
+	}
+
+	#[test]
+	fn hints() {
+		const FP256: u32 = PerfHints::FP128 | PerfHints::MOVU | PerfHints::FP256;
+
+		// Zen 1 does not set the FP256 hint, and its profile splits 256-bit operations.
+		let zen = dump(AMD, 0x00800F11, SIMDTier::AVX2, false);
+		assert_eq!(decide(&zen, PerfHints::new(PerfHints::FP128 | PerfHints::MOVU)), (SIMDTier::AVX2, 128, Reason::SplitFP256));
+
+		// Unknown AMD model: the hint alone decides.
+		let unknown = dump(AMD, 0x008F0FE0, SIMDTier::AVX2, false);
+		assert_eq!(decide(&unknown, PerfHints::new(PerfHints::FP128)), (SIMDTier::AVX2, 128, Reason::SplitFP256));
+		assert_eq!(decide(&unknown, PerfHints::new(FP256)), (SIMDTier::AVX2, 256, Reason::Native));
+		assert_eq!(decide(&unknown, PerfHints::empty()), (SIMDTier::AVX2, 256, Reason::Native));
+
+		// Intel does not define the leaf.
+		assert_eq!(PerfHints::from(CPUVendor::Intel, &zen), PerfHints::empty());
+	}
+
+	#[test]
+	fn without_avx() {
+		// Nehalem, SSE4.2.
+		let nehalem = dump(INTEL, 0x000106A5, SIMDTier::SSE42, false);
+		assert_eq!(decide(&nehalem, PerfHints::empty()), (SIMDTier::SSE42, 128, Reason::Native));
+
+		// Pentium M, 128-bit operations split in two.
+		let dothan = dump(INTEL, 0x000006D8, SIMDTier::SSE2, false);
+		assert_eq!(decide(&dothan, PerfHints::empty()), (SIMDTier::SSE2, 64, Reason::SplitFP128));
+
+		let scalar = dump(INTEL, 0x00000480, SIMDTier::Scalar, false);
+		assert_eq!(decide(&scalar, PerfHints::empty()), (SIMDTier::Scalar, 0, Reason::NoVector));
+	}
+}
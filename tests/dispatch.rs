@@ -0,0 +1,55 @@
+//! `#[cpu_dispatch]` expanded in a downstream crate.
+
+
+// This Source Code Form is subject to the terms of the
+// Mozilla Public License, v. 2.0. If a copy of the MPL
+// was not distributed with this file, You can obtain one
+// at https://mozilla.org/MPL/2.0/.
+
+
+
+#![cfg(feature = "macros")]
+
+use micro_cpuflags::cpu_dispatch;
+
+
+
+#[cpu_dispatch(avx2+fma, sse4.2, fallback)]
+fn sum(data: &[u8]) -> u32 {
+	data.iter().map(|&x| x as u32).sum()
+}
+
+
+#[cpu_dispatch(avx2, fallback)]
+fn swap((a, b): (u32, u32), [c, d]: [u8; 2], mut e: u32) -> (u32, u32, u8, u8, u32) {
+	e += 1;
+
+	(b, a, d, c, e)
+}
+
+
+#[cpu_dispatch(avx2, fallback)]
+fn first(data: &[u8]) -> Option<&u8> {
+	data.first()
+}
+
+
+
+#[test]
+fn values() {
+	assert_eq!(sum(&[1, 2, 3, 250]), 256);
+	assert_eq!(sum(&[]), 0);
+}
+
+#[test]
+fn patterns() {
+	assert_eq!(swap((1, 2), [3, 4], 5), (2, 1, 4, 3, 6));
+}
+
+#[test]
+fn borrowed() {
+	let data = [7u8, 8, 9];
+
+	assert_eq!(first(&data), Some(&data[0]));
+	assert_eq!(first(&[]), None);
+}